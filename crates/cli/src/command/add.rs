@@ -0,0 +1,34 @@
+use clap::{builder::PossibleValuesParser, Arg, ArgAction, Command};
+
+pub fn add_command() -> Command {
+    Command::new("add")
+        .about("Declare a link in the config file")
+        .arg(
+            Arg::new("source")
+                .value_name("SOURCE")
+                .help("A file or directory inside the dotfiles")
+                .required(true),
+        )
+        .arg(
+            Arg::new("target")
+                .value_name("TARGET")
+                .help("Where the link goes, written with $HOME when it is under the home directory")
+                .required(true),
+        )
+        .arg(section_arg().help("The section to add to, defaults to the one used on this OS"))
+        .arg(
+            Arg::new("link")
+                .long("link")
+                .help("Create the link right away")
+                .action(ArgAction::SetTrue),
+        )
+}
+
+pub fn section_arg() -> Arg {
+    Arg::new("section")
+        .long("section")
+        .value_name("SECTION")
+        .value_parser(PossibleValuesParser::new([
+            "links", "linux", "macos", "windows",
+        ]))
+}
@@ -0,0 +1,18 @@
+use clap::{Arg, Command};
+
+pub fn adopt_command() -> Command {
+    Command::new("adopt")
+        .about("Move an existing file or directory into the dotfiles and link it back")
+        .arg(
+            Arg::new("path")
+                .value_name("PATH")
+                .help("The file or directory to adopt")
+                .required(true),
+        )
+        .arg(
+            Arg::new("as")
+                .long("as")
+                .value_name("PATH")
+                .help("Where to put it inside the dotfiles, defaults to its file name"),
+        )
+}
@@ -0,0 +1,19 @@
+use clap::{Arg, ArgAction, Command};
+
+pub fn diff_command() -> Command {
+    Command::new("diff")
+        .about("Show how the existing targets differ from their sources")
+        .arg(
+            Arg::new("target")
+                .value_name("TARGET")
+                .help("Only compare these targets, defaults to every conflicting target")
+                .action(ArgAction::Append),
+        )
+}
+
+pub fn diff_args() -> Arg {
+    Arg::new("diff")
+        .long("diff")
+        .help("Show how each conflicting target differs from its source")
+        .action(ArgAction::SetTrue)
+}
@@ -0,0 +1,24 @@
+use clap::{Arg, ArgAction, Command};
+
+pub fn encrypt_command() -> Command {
+    Command::new("encrypt")
+        .about("Encrypt a secret into the dotfiles, and declare it as an encrypted link")
+        .arg(
+            Arg::new("path")
+                .value_name("PATH")
+                .help("The file to encrypt, it stays where it is as the target of the link")
+                .required(true),
+        )
+        .arg(
+            Arg::new("as")
+                .long("as")
+                .value_name("PATH")
+                .help("Where to put it inside the dotfiles, defaults to secrets/<file name>.age"),
+        )
+        .arg(
+            Arg::new("passphrase")
+                .long("passphrase")
+                .help("Encrypt with a passphrase instead of the identity file")
+                .action(ArgAction::SetTrue),
+        )
+}
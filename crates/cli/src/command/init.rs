@@ -0,0 +1,18 @@
+use clap::{Arg, ArgAction, Command};
+
+pub fn init_command() -> Command {
+    Command::new("init")
+        .about("Create a config file with commented examples in the current directory")
+        .arg(
+            Arg::new("nested")
+                .long("nested")
+                .help("Create .ezcfg/ezcfg.toml instead of .ezcfg.toml")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("scan")
+                .long("scan")
+                .help("Look for well-known dotfiles in $HOME and offer to adopt them")
+                .action(ArgAction::SetTrue),
+        )
+}
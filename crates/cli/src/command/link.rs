@@ -0,0 +1,41 @@
+use clap::{value_parser, Arg, ArgAction};
+
+pub fn atomic_args() -> Arg {
+    Arg::new("atomic")
+        .long("atomic")
+        .help("Roll back every change if any link fails")
+        .action(ArgAction::SetTrue)
+}
+
+pub fn interactive_args() -> Arg {
+    Arg::new("interactive")
+        .long("interactive")
+        .short('i')
+        .help("Ask what to do with each target which already exists")
+        .action(ArgAction::SetTrue)
+}
+
+pub fn force_unsafe_args() -> Arg {
+    Arg::new("force-unsafe")
+        .long("force-unsafe")
+        .help(
+            "Allow replacing the home directory, the dotfiles or targets outside the target roots",
+        )
+        .action(ArgAction::SetTrue)
+}
+
+pub fn jobs_args() -> Arg {
+    Arg::new("jobs")
+        .long("jobs")
+        .short('j')
+        .value_name("N")
+        .help("How many links to create at the same time, 1 links them one by one in order")
+        .value_parser(value_parser!(u64).range(1..))
+}
+
+pub fn timings_args() -> Arg {
+    Arg::new("timings")
+        .long("timings")
+        .help("Print the links which took the longest to create")
+        .action(ArgAction::SetTrue)
+}
@@ -0,0 +1,14 @@
+use clap::{builder::PossibleValuesParser, Arg, Command};
+
+pub fn list_command() -> Command {
+    Command::new("list")
+        .about("Print the links ezcfg acts on, after resolving sections and paths")
+        .arg(
+            Arg::new("format")
+                .long("format")
+                .value_name("FORMAT")
+                .help("How to print the links")
+                .value_parser(PossibleValuesParser::new(["table", "plain", "json"]))
+                .default_value("table"),
+        )
+}
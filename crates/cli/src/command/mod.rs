@@ -1,4 +1,14 @@
+mod add;
+mod adopt;
+mod diff;
+mod encrypt;
+mod init;
+mod link;
+mod list;
+mod prune;
+mod remove;
 mod version;
+mod watch;
 
 pub use version::version;
 
@@ -11,4 +21,20 @@ pub fn cli() -> Command {
         .arg_required_else_help(false)
         // Add args
         .arg(version::version_args())
+        .arg(link::atomic_args())
+        .arg(link::interactive_args())
+        .arg(link::force_unsafe_args())
+        .arg(link::jobs_args())
+        .arg(link::timings_args())
+        .arg(diff::diff_args())
+        // Add subcommands
+        .subcommand(add::add_command())
+        .subcommand(adopt::adopt_command())
+        .subcommand(diff::diff_command())
+        .subcommand(encrypt::encrypt_command())
+        .subcommand(init::init_command())
+        .subcommand(list::list_command())
+        .subcommand(prune::prune_command())
+        .subcommand(remove::remove_command())
+        .subcommand(watch::watch_command())
 }
@@ -0,0 +1,28 @@
+use clap::{value_parser, Arg, ArgAction, Command};
+
+pub fn prune_command() -> Command {
+    Command::new("prune")
+        .about("Remove links into the dotfiles which are no longer declared in the config")
+        .arg(
+            Arg::new("root")
+                .long("root")
+                .value_name("DIR")
+                .help("Directory to scan for links, defaults to $HOME and $XDG_CONFIG_HOME")
+                .action(ArgAction::Append),
+        )
+        .arg(
+            Arg::new("depth")
+                .long("depth")
+                .value_name("N")
+                .help("How deep to scan below each root")
+                .value_parser(value_parser!(usize))
+                .default_value("3"),
+        )
+        .arg(
+            Arg::new("yes")
+                .long("yes")
+                .short('y')
+                .help("Remove the links without asking for confirmation")
+                .action(ArgAction::SetTrue),
+        )
+}
@@ -0,0 +1,21 @@
+use clap::{Arg, ArgAction, Command};
+
+use super::add::section_arg;
+
+pub fn remove_command() -> Command {
+    Command::new("remove")
+        .about("Remove the links with the given source or target from the config file")
+        .arg(
+            Arg::new("path")
+                .value_name("PATH")
+                .help("The source or the target of the link")
+                .required(true),
+        )
+        .arg(section_arg().help("The section to remove from, defaults to every section"))
+        .arg(
+            Arg::new("unlink")
+                .long("unlink")
+                .help("Also remove the links which are in place")
+                .action(ArgAction::SetTrue),
+        )
+}
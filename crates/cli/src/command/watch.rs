@@ -0,0 +1,14 @@
+use clap::{value_parser, Arg, Command};
+
+pub fn watch_command() -> Command {
+    Command::new("watch")
+        .about("Keep watching the config file and the dotfiles, and link what has changed")
+        .arg(
+            Arg::new("debounce")
+                .long("debounce")
+                .value_name("MS")
+                .help("How long to wait for changes to settle before applying them")
+                .value_parser(value_parser!(u64))
+                .default_value("300"),
+        )
+}
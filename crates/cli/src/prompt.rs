@@ -0,0 +1,27 @@
+use std::io::{stdin, stdout, Write};
+
+/// Ask a yes/no question on the terminal, anything but `y` or `yes` means no
+pub fn confirm(question: &str) -> bool {
+    matches!(ask(&format!("{} [y/N] ", question)).as_str(), "y" | "yes")
+}
+
+/// Print `question` and read a trimmed, lowercased line from stdin
+///
+/// An empty string is returned when stdin is closed.
+pub fn ask(question: &str) -> String {
+    print!("{}", question);
+    let _ = stdout().flush();
+
+    let mut answer = String::new();
+    if stdin().read_line(&mut answer).is_err() {
+        return String::new();
+    }
+    answer.trim().to_lowercase()
+}
+
+/// Print `question` and read a line from the terminal without echoing it
+///
+/// An empty string is returned when there is no terminal to read from.
+pub fn ask_secret(question: &str) -> String {
+    rpassword::prompt_password(question).unwrap_or_default()
+}
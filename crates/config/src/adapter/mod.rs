@@ -1,25 +1,84 @@
-use std::{env::current_dir, path::PathBuf, vec};
+use std::{collections::BTreeMap, env::current_dir, path::PathBuf, vec};
 
 use ezcfg_cli::warn;
 use serde::Deserialize;
 use toml::from_str as toml_from_str;
 
-use crate::{error::ReadConfigError, Config, Links};
+use crate::{
+    error::ReadConfigError, link_transform::resolve_target, Config, Hooks, HooksConfig,
+    LinkOptions, Links, Section,
+};
 
 #[derive(Debug, Default, Deserialize)]
 struct ConfigStruct {
     /// Whether to rewrite the target file if it already exists
     rewrite: Option<bool>,
 
+    /// Shell commands to run around linking
+    hooks: Option<HooksConfig>,
+
+    /// Directories the targets to replace must be inside
+    target_roots: Option<Vec<String>>,
+
+    /// How many links to create at the same time
+    jobs: Option<usize>,
+
     /// Cross-platform shared configuration
-    links: Option<Links>,
+    links: Option<Vec<RawLink>>,
 
     /// System-specific configuration
-    linux: Option<Links>,
+    linux: Option<Vec<RawLink>>,
     /// System-specific configuration
-    macos: Option<Links>,
+    macos: Option<Vec<RawLink>>,
     /// System-specific configuration
-    windows: Option<Links>,
+    windows: Option<Vec<RawLink>>,
+}
+
+/// A link is either a `[source, target]` pair or a table with extra settings
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum RawLink {
+    Pair(String, String),
+    Table {
+        source: String,
+        target: String,
+        #[serde(default)]
+        hooks: Hooks,
+        #[serde(default)]
+        encrypted: bool,
+        mode: Option<u32>,
+        dir_mode: Option<u32>,
+    },
+}
+
+fn split_raw_links(raw_links: Vec<RawLink>) -> (Links, BTreeMap<String, LinkOptions>) {
+    let mut links = vec![];
+    let mut link_options = BTreeMap::new();
+
+    for raw_link in raw_links {
+        match raw_link {
+            RawLink::Pair(source, target) => links.push((source, target)),
+            RawLink::Table {
+                source,
+                target,
+                hooks,
+                encrypted,
+                mode,
+                dir_mode,
+            } => {
+                let options = LinkOptions {
+                    hooks,
+                    encrypted,
+                    mode,
+                    dir_mode,
+                };
+                link_options.insert(target.clone(), options);
+                links.push((source, target));
+            }
+        }
+    }
+
+    (links, link_options)
 }
 
 pub fn adapter(path: &PathBuf, kind: &str, raw: &str) -> Result<Config, ReadConfigError> {
@@ -40,32 +99,49 @@ pub fn adapter(path: &PathBuf, kind: &str, raw: &str) -> Result<Config, ReadConf
     let config_struct: ConfigStruct = config_struct.unwrap();
 
     let rewrite = config_struct.rewrite.unwrap_or(false);
+    let hooks = config_struct.hooks.unwrap_or_default();
+    let target_roots = config_struct
+        .target_roots
+        .unwrap_or_default()
+        .iter()
+        .map(|root| PathBuf::from(resolve_target(root)))
+        .collect();
+
+    let system_specified_links = match Section::current_os() {
+        Some(Section::Linux) => config_struct.linux,
+        Some(Section::Macos) => config_struct.macos,
+        Some(Section::Windows) => config_struct.windows,
+        _ => None,
+    };
 
-    let links = {
-        let system_specified_links = {
-            if cfg!(target_os = "linux") {
-                config_struct.linux
-            } else if cfg!(target_os = "macos") {
-                config_struct.macos
-            } else if cfg!(target_os = "windows") {
-                config_struct.windows
-            } else {
-                None
-            }
-        };
-
-        if let Some(links) = system_specified_links {
-            links
-        } else if let Some(links) = config_struct.links {
-            links
-        } else {
-            let msg = format!("No links found in the config file: {:?}", path);
-            warn!(msg);
-            vec![]
-        }
+    let (section, links) = if let Some(links) = system_specified_links {
+        (Section::current_os().unwrap(), links)
+    } else if let Some(links) = config_struct.links {
+        (Section::Links, links)
+    } else {
+        let msg = format!("No links found in the config file: {:?}", path);
+        warn!(msg);
+        (Section::Links, vec![])
     };
 
-    Ok(Config::new(links, rewrite))
+    let (links, link_options) = split_raw_links(links);
+
+    if config_struct.jobs == Some(0) {
+        return Err(ReadConfigError::InvalidValue(
+            "jobs must be at least 1".to_string(),
+        ));
+    }
+
+    Ok(Config {
+        rewrite,
+        links,
+        section,
+        hooks,
+        link_options,
+        target_roots,
+        jobs: config_struct.jobs,
+        path: path.to_owned(),
+    })
 }
 
 #[cfg(test)]
@@ -73,6 +149,7 @@ mod tests {
     use std::str::FromStr;
 
     use super::*;
+    use crate::FailurePolicy;
 
     fn concat_pwd(path: &str) -> String {
         current_dir()
@@ -119,6 +196,109 @@ mod tests {
         adapter(&PathBuf::default(), "toml", "").unwrap();
     }
 
+    #[test]
+    fn it_should_parse_hooks() {
+        let raw = r#"
+            links = [
+                ["a", "b"],
+                { source = "c", target = "d", hooks = { pre_link = ["e"], post_link = ["f"] } },
+            ]
+
+            [hooks]
+            post_link = ["fc-cache"]
+            timeout = 5
+            on_failure = "continue"
+        "#;
+
+        let config = adapter(&PathBuf::default(), "toml", raw).unwrap();
+
+        assert_eq!(
+            config.links,
+            vec![
+                ("a".to_string(), "b".to_string()),
+                ("c".to_string(), "d".to_string())
+            ]
+        );
+        assert_eq!(config.hooks.hooks.pre_link, Vec::<String>::new());
+        assert_eq!(config.hooks.hooks.post_link, vec!["fc-cache"]);
+        assert_eq!(config.hooks.timeout, 5);
+        assert_eq!(config.hooks.on_failure, FailurePolicy::Continue);
+
+        let options = config.link_options("d").unwrap();
+        assert_eq!(options.hooks.pre_link, vec!["e"]);
+        assert_eq!(options.hooks.post_link, vec!["f"]);
+        assert!(!config.is_encrypted("d"));
+    }
+
+    #[test]
+    fn it_should_parse_encrypted_links() {
+        let raw = r#"
+            links = [
+                { source = "secrets/netrc.age", target = "n", encrypted = true },
+                ["a", "b"],
+            ]
+        "#;
+
+        let config = adapter(&PathBuf::default(), "toml", raw).unwrap();
+
+        assert!(config.is_encrypted("n"));
+        assert!(!config.is_encrypted("b"));
+    }
+
+    #[test]
+    fn it_should_parse_target_roots() {
+        let raw = r#"
+            target_roots = ["/etc", "/opt/tools"]
+            links = []
+        "#;
+
+        let config = adapter(&PathBuf::default(), "toml", raw).unwrap();
+
+        assert_eq!(
+            config.target_roots,
+            vec![PathBuf::from("/etc"), PathBuf::from("/opt/tools")]
+        );
+        assert!(adapter(&PathBuf::default(), "toml", "")
+            .unwrap()
+            .target_roots
+            .is_empty());
+    }
+
+    #[test]
+    fn it_should_parse_jobs() {
+        let config = adapter(&PathBuf::default(), "toml", "jobs = 1").unwrap();
+        assert_eq!(config.jobs, Some(1));
+
+        assert_eq!(
+            adapter(&PathBuf::default(), "toml", "jobs = 0").unwrap_err(),
+            ReadConfigError::InvalidValue("jobs must be at least 1".to_string())
+        );
+    }
+
+    #[test]
+    fn it_should_parse_octal_modes() {
+        let raw = r#"
+            links = [
+                { source = "ssh", target = "s", mode = 0o600, dir_mode = 0o700 },
+            ]
+        "#;
+
+        let config = adapter(&PathBuf::default(), "toml", raw).unwrap();
+
+        let options = config.link_options("s").unwrap();
+        assert_eq!(options.mode, Some(0o600));
+        assert_eq!(options.dir_mode, Some(0o700));
+    }
+
+    #[test]
+    fn it_should_use_default_hooks_config() {
+        let config = adapter(&PathBuf::default(), "toml", "").unwrap();
+
+        assert!(config.hooks.hooks.is_empty());
+        assert_eq!(config.hooks.timeout, 60);
+        assert_eq!(config.hooks.on_failure, FailurePolicy::Abort);
+    }
+
     #[cfg(target_os = "macos")]
     #[test]
     fn it_should_use_system_specified_first_on_macos() {
@@ -179,6 +359,7 @@ mod tests {
             "#;
             let config = adapter(&PathBuf::default(), "toml", raw).unwrap();
 
+            assert_eq!(config.section, Section::Linux);
             assert_eq!(config.links.len(), 2);
             assert_eq!(config.links[0].0, "m_a");
             assert_eq!(config.links[0].1, "m_b");
@@ -199,6 +380,7 @@ mod tests {
             "#;
             let config = adapter(&PathBuf::default(), "toml", raw).unwrap();
 
+            assert_eq!(config.section, Section::Links);
             assert_eq!(config.links.len(), 2);
             assert_eq!(config.links[0].0, "a");
             assert_eq!(config.links[0].1, "b");
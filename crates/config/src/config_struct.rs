@@ -1,22 +1,71 @@
-use std::{fs::read_to_string, path::PathBuf};
+use std::{collections::BTreeMap, fs::read_to_string, path::PathBuf};
 
 use serde::Deserialize;
 
 use crate::error::ReadConfigError::*;
-use crate::link_transform::link_transform;
-use crate::{adapter::adapter, error::ReadConfigError};
+use crate::link_transform::{link_options_transform, link_transform};
+use crate::{adapter::adapter, error::ReadConfigError, Hooks, HooksConfig, Section};
 
 pub type Links = Vec<(String, String)>;
 
-#[derive(Debug, Default, Deserialize)]
+/// Settings attached to a single link
+///
+/// Declared with the table form of a link in the config file:
+///
+/// ```toml
+/// links = [
+///     { source = "nvim", target = "$HOME/.config/nvim", hooks = { post_link = ["nvim --headless +qa"] } },
+/// ]
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize)]
+pub struct LinkOptions {
+    pub hooks: Hooks,
+    /// The source is encrypted with age, and is decrypted to the target instead of being linked
+    pub encrypted: bool,
+    /// Permissions of the files, written as an octal number like `0o600`
+    pub mode: Option<u32>,
+    /// Permissions of the directories, written as an octal number like `0o700`
+    pub dir_mode: Option<u32>,
+}
+
+#[derive(Debug, Default, Clone, Deserialize)]
 pub struct Config {
     pub rewrite: bool,
     pub links: Links,
+    /// The section `links` have been read from
+    pub section: Section,
+
+    /// Global hooks and how to run them
+    pub hooks: HooksConfig,
+    /// Per-link settings, keyed by the resolved target
+    pub link_options: BTreeMap<String, LinkOptions>,
+    /// Directories the targets to replace must be inside, the home directory when empty
+    pub target_roots: Vec<PathBuf>,
+    /// How many links to create at the same time, one per CPU when unset
+    pub jobs: Option<usize>,
+
+    /// The config file it has been read from
+    pub path: PathBuf,
 }
 
 impl Config {
     pub fn new(links: Links, rewrite: bool) -> Self {
-        Config { links, rewrite }
+        Config {
+            links,
+            rewrite,
+            ..Default::default()
+        }
+    }
+
+    /// Get the settings of the link whose target is `target`
+    pub fn link_options(&self, target: &str) -> Option<&LinkOptions> {
+        self.link_options.get(target)
+    }
+
+    /// Whether the link whose target is `target` is a secret to decrypt
+    pub fn is_encrypted(&self, target: &str) -> bool {
+        self.link_options(target)
+            .is_some_and(|options| options.encrypted)
     }
 }
 
@@ -41,9 +90,12 @@ impl TryFrom<&PathBuf> for Config {
         let raw_config = adapter(path, ext, &content)?;
 
         let links = link_transform(raw_config.links);
+        let link_options = link_options_transform(raw_config.link_options);
 
         Ok(Config {
             links,
+            link_options,
+            path: path.to_owned(),
             ..raw_config
         })
     }
@@ -59,7 +111,7 @@ mod tests {
 
     fn test_with_temp_dir<F>(f: F)
     where
-        F: FnOnce(&TempDir) -> (),
+        F: FnOnce(&TempDir),
     {
         let temp_dir = TempDir::new().unwrap();
         f(&temp_dir);
@@ -93,6 +145,7 @@ mod tests {
             let config_path = temp_dir.child("ezcfg.toml").path().to_path_buf();
             let config = Config::try_from(&config_path).unwrap();
 
+            assert_eq!(config.path, config_path);
             assert_eq!(config.links.len(), 2);
             assert_eq!(config.links[0].0, concat_pwd("a"));
             assert_eq!(config.links[0].1, "b");
@@ -100,4 +153,32 @@ mod tests {
             assert_eq!(config.links[1].1, "d");
         })
     }
+
+    #[test]
+    fn it_should_resolve_link_options_by_target() {
+        test_with_temp_dir(|temp_dir| {
+            temp_dir
+                .child("ezcfg.toml")
+                .write_str(
+                    r#"
+                        links = [
+                            ["a", "b"],
+                            { source = "c", target = "$HOME/d", hooks = { post_link = ["true"] } },
+                        ]
+                    "#,
+                )
+                .unwrap();
+
+            let config_path = temp_dir.child("ezcfg.toml").path().to_path_buf();
+            let config = Config::try_from(&config_path).unwrap();
+
+            let target = format!("{}/d", std::env::var("HOME").unwrap());
+            assert_eq!(config.links[1], (concat_pwd("c"), target.clone()));
+            assert_eq!(
+                config.link_options(&target).unwrap().hooks.post_link,
+                vec!["true"]
+            );
+            assert!(config.link_options("b").is_none());
+        })
+    }
 }
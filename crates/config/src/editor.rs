@@ -0,0 +1,535 @@
+use std::{
+    fmt::{self, Display, Formatter},
+    fs::{read_to_string, write},
+    path::{Path, PathBuf},
+};
+
+use serde::Deserialize;
+use toml_edit::{Array, DocumentMut, InlineTable, Value};
+
+use crate::error::EditConfigError::{self, *};
+
+/// An array of links in the config file
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Section {
+    /// Cross-platform shared links
+    #[default]
+    Links,
+    Linux,
+    Macos,
+    Windows,
+}
+
+impl Section {
+    pub const ALL: [Section; 4] = [
+        Section::Links,
+        Section::Linux,
+        Section::Macos,
+        Section::Windows,
+    ];
+
+    /// The system-specific section of the current OS
+    pub fn current_os() -> Option<Self> {
+        if cfg!(target_os = "linux") {
+            Some(Section::Linux)
+        } else if cfg!(target_os = "macos") {
+            Some(Section::Macos)
+        } else if cfg!(target_os = "windows") {
+            Some(Section::Windows)
+        } else {
+            None
+        }
+    }
+
+    pub fn key(&self) -> &'static str {
+        match self {
+            Section::Links => "links",
+            Section::Linux => "linux",
+            Section::Macos => "macos",
+            Section::Windows => "windows",
+        }
+    }
+}
+
+impl Display for Section {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{}", self.key())
+    }
+}
+
+impl TryFrom<&str> for Section {
+    type Error = EditConfigError;
+
+    fn try_from(key: &str) -> Result<Self, Self::Error> {
+        match key {
+            "links" => Ok(Section::Links),
+            "linux" => Ok(Section::Linux),
+            "macos" => Ok(Section::Macos),
+            "windows" => Ok(Section::Windows),
+            _ => Err(InvalidSection(key.to_string())),
+        }
+    }
+}
+
+/// Edit the links of a config file while preserving its formatting and comments
+///
+/// # Example
+///
+/// ```rust
+/// use ezcfg_config::{ConfigEditor, Section};
+///
+/// let mut editor = ConfigEditor::parse(
+///     "links = [\n    # shell\n    [\"zsh/.zshrc\", \"$HOME/.zshrc\"],\n]\n",
+/// )
+/// .unwrap();
+/// editor.add_link(Section::Links, "tmux/.tmux.conf", "$HOME/.tmux.conf").unwrap();
+///
+/// assert_eq!(
+///     editor.to_string(),
+///     "links = [\n    # shell\n    [\"zsh/.zshrc\", \"$HOME/.zshrc\"],\n    [\"tmux/.tmux.conf\", \"$HOME/.tmux.conf\"],\n]\n"
+/// );
+/// ```
+#[derive(Debug)]
+pub struct ConfigEditor {
+    path: PathBuf,
+    doc: DocumentMut,
+}
+
+impl ConfigEditor {
+    pub fn open(path: &Path) -> Result<Self, EditConfigError> {
+        let content = read_to_string(path).map_err(|_| FailedToReadFile(path.to_owned()))?;
+        let doc = content
+            .parse::<DocumentMut>()
+            .map_err(|e| FailedToParseFile(path.to_owned(), e.to_string()))?;
+
+        Ok(ConfigEditor {
+            path: path.to_owned(),
+            doc,
+        })
+    }
+
+    /// Edit a config which is not backed by a file
+    pub fn parse(content: &str) -> Result<Self, EditConfigError> {
+        let doc = content
+            .parse::<DocumentMut>()
+            .map_err(|e| FailedToParseFile(PathBuf::new(), e.to_string()))?;
+
+        Ok(ConfigEditor {
+            path: PathBuf::new(),
+            doc,
+        })
+    }
+
+    /// Write the edited config back to the file it has been opened from
+    pub fn save(&self) -> Result<(), EditConfigError> {
+        write(&self.path, self.doc.to_string()).map_err(|_| FailedToWriteFile(self.path.clone()))
+    }
+
+    /// The section ezcfg reads the links from on the current OS
+    ///
+    /// The system-specific section wins over `links` when it exists.
+    pub fn active_section(&self) -> Section {
+        Section::current_os()
+            .filter(|section| self.doc.contains_key(section.key()))
+            .unwrap_or(Section::Links)
+    }
+
+    /// Append a `[source, target]` pair to `section`, creating the section if needed
+    pub fn add_link(
+        &mut self,
+        section: Section,
+        source: &str,
+        target: &str,
+    ) -> Result<(), EditConfigError> {
+        let mut link = Array::from_iter([source, target]);
+        link.decor_mut().clear();
+        self.push_link(section, Value::Array(link))
+    }
+
+    /// Append a `{ source, target, encrypted = true }` table to `section`,
+    /// creating the section if needed
+    pub fn add_encrypted_link(
+        &mut self,
+        section: Section,
+        source: &str,
+        target: &str,
+    ) -> Result<(), EditConfigError> {
+        let mut link = InlineTable::new();
+        link.insert("source", source.into());
+        link.insert("target", target.into());
+        link.insert("encrypted", true.into());
+        self.push_link(section, Value::InlineTable(link))
+    }
+
+    fn push_link(&mut self, section: Section, link: Value) -> Result<(), EditConfigError> {
+        let key = section.key();
+        if !self.doc.contains_key(key) {
+            self.doc[key] = toml_edit::value(Array::new());
+        }
+        let array = self.doc[key]
+            .as_array_mut()
+            .ok_or_else(|| InvalidSection(key.to_string()))?;
+
+        push_preserving_layout(array, link);
+
+        Ok(())
+    }
+
+    /// The `(source, target)` of every link in `section`, as written in the config
+    pub fn links(&self, section: Section) -> Vec<(String, String)> {
+        self.doc
+            .get(section.key())
+            .and_then(|item| item.as_array())
+            .map(|array| array.iter().filter_map(parse_link).collect())
+            .unwrap_or_default()
+    }
+
+    /// Remove the links of `section` accepted by `predicate`, and return them
+    ///
+    /// Both `[source, target]` pairs and tables are matched,
+    /// comments are kept unless they are on the line of a removed link.
+    pub fn remove_links<F>(
+        &mut self,
+        section: Section,
+        predicate: F,
+    ) -> Result<Vec<(String, String)>, EditConfigError>
+    where
+        F: Fn(&str, &str) -> bool,
+    {
+        let key = section.key();
+        let array = match self.doc.get_mut(key) {
+            Some(item) => item
+                .as_array_mut()
+                .ok_or_else(|| InvalidSection(key.to_string()))?,
+            None => return Ok(vec![]),
+        };
+
+        let matched = array
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, value)| parse_link(value).map(|link| (idx, link)))
+            .filter(|(_, (source, target))| predicate(source, target))
+            .collect::<Vec<_>>();
+
+        for (idx, _) in matched.iter().rev() {
+            remove_preserving_layout(array, *idx);
+        }
+
+        Ok(matched.into_iter().map(|(_, link)| link).collect())
+    }
+}
+
+impl Display for ConfigEditor {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{}", self.doc)
+    }
+}
+
+/// Push `value` so that it looks like the values already in `array`
+///
+/// A comment on the line of the last value stays on that line,
+/// an empty array becomes a multi-line array.
+fn push_preserving_layout(array: &mut Array, mut value: Value) {
+    let last_prefix = array
+        .iter()
+        .last()
+        .map(|last| raw_str(last.decor().prefix()).to_string());
+
+    let prefix = match last_prefix {
+        None => {
+            // Comments of an empty array go before the first value
+            let trailing = raw_str(Some(array.trailing())).to_string();
+            let comments = match trailing.rfind('\n') {
+                Some(idx) => &trailing[..idx],
+                None => "",
+            };
+
+            array.set_trailing_comma(true);
+            array.set_trailing("\n");
+            format!("{}\n    ", comments)
+        }
+        Some(last_prefix) if last_prefix.contains('\n') => {
+            let indent = last_prefix
+                .rsplit('\n')
+                .next()
+                .unwrap_or_default()
+                .to_string();
+            let trailing = raw_str(Some(array.trailing())).to_string();
+            let (same_line, rest) = split_first_line(&trailing);
+
+            let prefix = match same_line.ends_with('\n') {
+                true => format!("{}{}", same_line, indent),
+                false => format!("{}\n{}", same_line, indent),
+            };
+            array.set_trailing(format!("\n{}", rest));
+            prefix
+        }
+        Some(_) => " ".to_string(),
+    };
+
+    value.decor_mut().set_prefix(prefix);
+    value.decor_mut().set_suffix("");
+    array.push_formatted(value);
+}
+
+/// Remove the value at `idx` along with the comment on its line
+fn remove_preserving_layout(array: &mut Array, idx: usize) {
+    let removed = array.remove(idx);
+    let removed_prefix = raw_str(removed.decor().prefix());
+
+    // Keep what comes before the removed value, except its indentation
+    let kept = match removed_prefix.rfind('\n') {
+        Some(nl) => &removed_prefix[..=nl],
+        None => "",
+    };
+
+    match array.get_mut(idx) {
+        Some(next) => {
+            let next_prefix = raw_str(next.decor().prefix()).to_string();
+            let prefix = match removed_prefix.contains('\n') {
+                true => format!("{}{}", kept, split_first_line(&next_prefix).1),
+                false => removed_prefix.to_string(),
+            };
+            next.decor_mut().set_prefix(prefix);
+        }
+        None => {
+            let trailing = raw_str(Some(array.trailing())).to_string();
+            if removed_prefix.contains('\n') {
+                array.set_trailing(format!("{}{}", kept, split_first_line(&trailing).1));
+            }
+            if array.is_empty() && !raw_str(Some(array.trailing())).contains('\n') {
+                array.set_trailing_comma(false);
+            }
+        }
+    }
+}
+
+fn parse_link(value: &Value) -> Option<(String, String)> {
+    match value {
+        Value::Array(pair) if pair.len() == 2 => Some((
+            pair.get(0)?.as_str()?.to_string(),
+            pair.get(1)?.as_str()?.to_string(),
+        )),
+        Value::InlineTable(table) => Some((
+            table.get("source")?.as_str()?.to_string(),
+            table.get("target")?.as_str()?.to_string(),
+        )),
+        _ => None,
+    }
+}
+
+/// Split after the first line break, the first part is the rest of the current line
+fn split_first_line(s: &str) -> (&str, &str) {
+    match s.find('\n') {
+        Some(idx) => s.split_at(idx + 1),
+        None => (s, ""),
+    }
+}
+
+fn raw_str(raw: Option<&toml_edit::RawString>) -> &str {
+    raw.and_then(|raw| raw.as_str()).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn add(content: &str, section: Section) -> String {
+        let mut editor = ConfigEditor::parse(content).unwrap();
+        editor.add_link(section, "c", "d").unwrap();
+        editor.to_string()
+    }
+
+    #[test]
+    fn it_should_append_to_multi_line_array() {
+        let content = r#"# my dotfiles
+rewrite = true
+
+links = [
+  # shell
+  ["a", "b"], # the shell
+]
+
+[hooks]
+post_link = ["true"]
+"#;
+
+        assert_eq!(
+            add(content, Section::Links),
+            r#"# my dotfiles
+rewrite = true
+
+links = [
+  # shell
+  ["a", "b"], # the shell
+  ["c", "d"],
+]
+
+[hooks]
+post_link = ["true"]
+"#
+        );
+    }
+
+    #[test]
+    fn it_should_keep_standalone_comments_at_the_end() {
+        let content = "links = [\n    [\"a\", \"b\"],\n    # [\"x\", \"y\"],\n]\n";
+
+        assert_eq!(
+            add(content, Section::Links),
+            "links = [\n    [\"a\", \"b\"],\n    [\"c\", \"d\"],\n    # [\"x\", \"y\"],\n]\n"
+        );
+    }
+
+    #[test]
+    fn it_should_keep_comments_of_empty_array_before_the_value() {
+        let content = "links = [\n    # [\"x\", \"y\"],\n]\n";
+
+        assert_eq!(
+            add(content, Section::Links),
+            "links = [\n    # [\"x\", \"y\"],\n    [\"c\", \"d\"],\n]\n"
+        );
+    }
+
+    #[test]
+    fn it_should_append_to_single_line_array() {
+        assert_eq!(
+            add("links = [[\"a\", \"b\"]]\n", Section::Links),
+            "links = [[\"a\", \"b\"], [\"c\", \"d\"]]\n"
+        );
+    }
+
+    #[test]
+    fn it_should_create_missing_section() {
+        assert_eq!(
+            add(
+                "rewrite = true\n\n[hooks]\npost_link = []\n",
+                Section::Linux
+            ),
+            "rewrite = true\nlinux = [\n    [\"c\", \"d\"],\n]\n\n[hooks]\npost_link = []\n"
+        );
+    }
+
+    #[test]
+    fn it_should_append_encrypted_link_as_table() {
+        let mut editor = ConfigEditor::parse("links = [\n    [\"a\", \"b\"],\n]\n").unwrap();
+        editor
+            .add_encrypted_link(Section::Links, "secrets/netrc.age", "$HOME/.netrc")
+            .unwrap();
+
+        assert_eq!(
+            editor.to_string(),
+            "links = [\n    [\"a\", \"b\"],\n    { source = \"secrets/netrc.age\", target = \"$HOME/.netrc\", encrypted = true },\n]\n"
+        );
+    }
+
+    fn remove(content: &str, target: &str) -> String {
+        let mut editor = ConfigEditor::parse(content).unwrap();
+        let removed = editor
+            .remove_links(Section::Links, |_, t| t == target)
+            .unwrap();
+        assert_eq!(removed.len(), 1);
+        editor.to_string()
+    }
+
+    #[test]
+    fn it_should_list_pairs_and_tables() {
+        let editor = ConfigEditor::parse(
+            "links = [[\"a\", \"b\"], { source = \"c\", target = \"d\", hooks = {} }]",
+        )
+        .unwrap();
+
+        assert_eq!(
+            editor.links(Section::Links),
+            vec![
+                ("a".to_string(), "b".to_string()),
+                ("c".to_string(), "d".to_string())
+            ]
+        );
+        assert!(editor.links(Section::Linux).is_empty());
+    }
+
+    #[test]
+    fn it_should_remove_from_multi_line_array() {
+        let content = r#"links = [
+    # shell
+    ["a", "b"], # the shell
+    # editor
+    { source = "c", target = "d" }, # the editor
+    ["e", "f"],
+]
+"#;
+
+        assert_eq!(
+            remove(content, "d"),
+            r#"links = [
+    # shell
+    ["a", "b"], # the shell
+    # editor
+    ["e", "f"],
+]
+"#
+        );
+        assert_eq!(
+            remove(content, "f"),
+            r#"links = [
+    # shell
+    ["a", "b"], # the shell
+    # editor
+    { source = "c", target = "d" }, # the editor
+]
+"#
+        );
+        assert_eq!(
+            remove(content, "b"),
+            r#"links = [
+    # shell
+    # editor
+    { source = "c", target = "d" }, # the editor
+    ["e", "f"],
+]
+"#
+        );
+    }
+
+    #[test]
+    fn it_should_remove_from_single_line_array() {
+        let content = "links = [[\"a\", \"b\"], [\"c\", \"d\"], [\"e\", \"f\"]]\n";
+
+        assert_eq!(
+            remove(content, "b"),
+            "links = [[\"c\", \"d\"], [\"e\", \"f\"]]\n"
+        );
+        assert_eq!(
+            remove(content, "d"),
+            "links = [[\"a\", \"b\"], [\"e\", \"f\"]]\n"
+        );
+        assert_eq!(
+            remove(content, "f"),
+            "links = [[\"a\", \"b\"], [\"c\", \"d\"]]\n"
+        );
+        assert_eq!(remove("links = [[\"a\", \"b\"]]", "b"), "links = []\n");
+    }
+
+    #[test]
+    fn it_should_refuse_invalid_section() {
+        let mut editor = ConfigEditor::parse("links = 1").unwrap();
+
+        assert_eq!(
+            editor.add_link(Section::Links, "c", "d"),
+            Err(InvalidSection("links".to_string()))
+        );
+    }
+
+    #[test]
+    fn it_should_use_system_specified_section_when_present() {
+        let editor = ConfigEditor::parse("links = []").unwrap();
+        assert_eq!(editor.active_section(), Section::Links);
+
+        if let Some(section) = Section::current_os() {
+            let editor = ConfigEditor::parse(&format!("links = []\n{} = []", section)).unwrap();
+            assert_eq!(editor.active_section(), section);
+        }
+    }
+}
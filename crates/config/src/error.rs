@@ -1,4 +1,7 @@
-use std::path::PathBuf;
+use std::{
+    fmt::{self, Display, Formatter},
+    path::PathBuf,
+};
 
 #[derive(Debug, PartialEq, Eq)]
 pub enum ReadConfigError {
@@ -6,4 +9,59 @@ pub enum ReadConfigError {
     FailedToReadFile(PathBuf),
 
     UnsupportedConfigFile(String),
+
+    /// A setting has a value it can not take, described by the message
+    InvalidValue(String),
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum EditConfigError {
+    FailedToReadFile(PathBuf),
+    FailedToParseFile(PathBuf, String),
+    FailedToWriteFile(PathBuf),
+
+    /// The section exists but is not an array of links
+    InvalidSection(String),
+}
+
+/// Why a set of resolved links can not be applied safely
+#[derive(Debug, PartialEq, Eq)]
+pub enum LinkGraphError {
+    /// The target lies inside the dotfiles, the link would be written among the sources
+    TargetInsideDotfiles(String),
+    /// Several links share the same target
+    SameTarget(String),
+    /// The target of `inner` lies inside the target of `outer`,
+    /// it would be written through it into the dotfiles
+    NestedTarget { outer: String, inner: String },
+    /// The source and the target of a link contain each other
+    SelfLoop(String),
+    /// The source of each link lies inside the target of the next one, given by their targets
+    Cycle(Vec<String>),
+}
+
+impl Display for LinkGraphError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            LinkGraphError::TargetInsideDotfiles(target) => {
+                write!(f, "{} is inside the dotfiles", target)
+            }
+            LinkGraphError::SameTarget(target) => {
+                write!(f, "{} is the target of several links", target)
+            }
+            LinkGraphError::NestedTarget { outer, inner } => {
+                write!(
+                    f,
+                    "{} is inside {}, which links it into the dotfiles",
+                    inner, outer
+                )
+            }
+            LinkGraphError::SelfLoop(target) => write!(f, "{} overlaps its own source", target),
+            LinkGraphError::Cycle(targets) => write!(
+                f,
+                "the sources and targets of {} depend on each other",
+                targets.join(", ")
+            ),
+        }
+    }
 }
@@ -0,0 +1,292 @@
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap},
+    path::{Component, Path, PathBuf},
+};
+
+use crate::{error::LinkGraphError, Links};
+
+/// Check how the resolved `links` overlap, and order them so that every link comes
+/// after the links whose target contains its source or its target
+///
+/// Targets inside `dotfiles`, shared targets, nested targets which would be written
+/// through their parent link into `dotfiles`, sources overlapping their own target
+/// and links depending on each other are reported, all of them at once.
+///
+/// # Example
+///
+/// ```rust
+/// use ezcfg_config::{order_links, LinkGraphError};
+/// use std::path::Path;
+///
+/// let link = |source: &str, target: &str| (source.to_string(), target.to_string());
+/// let dotfiles = Path::new("/dotfiles");
+///
+/// // The source of the first link only exists once the second one is linked
+/// let links = vec![
+///     link("/home/.config/nvim/lua", "/home/.nvimlua"),
+///     link("/dotfiles/nvim", "/home/.config/nvim"),
+/// ];
+/// let ordered = order_links(&links, dotfiles).unwrap();
+/// assert_eq!(ordered, vec![links[1].clone(), links[0].clone()]);
+///
+/// // The target of the first link only exists once the second one is linked
+/// let links = vec![
+///     link("/dotfiles/cache.toml", "/home/.cache/app.toml"),
+///     link("/mnt/cache", "/home/.cache"),
+/// ];
+/// let ordered = order_links(&links, dotfiles).unwrap();
+/// assert_eq!(ordered, vec![links[1].clone(), links[0].clone()]);
+///
+/// let links = vec![link("/dotfiles/zsh", "/dotfiles/zsh/.zshrc")];
+/// assert!(order_links(&links, dotfiles).is_err());
+/// ```
+pub fn order_links(links: &Links, dotfiles: &Path) -> Result<Links, Vec<LinkGraphError>> {
+    let dotfiles = normalize(dotfiles);
+    let paths = links
+        .iter()
+        .map(|(source, target)| (normalize(Path::new(source)), normalize(Path::new(target))))
+        .collect::<Vec<_>>();
+
+    let mut by_target = HashMap::<&Path, Vec<usize>>::new();
+    for (idx, (_, target)) in paths.iter().enumerate() {
+        by_target.entry(target).or_default().push(idx);
+    }
+
+    let mut errors = vec![];
+    for (idx, (source, target)) in paths.iter().enumerate() {
+        let raw_target = &links[idx].1;
+
+        if target.starts_with(&dotfiles) {
+            errors.push(LinkGraphError::TargetInsideDotfiles(raw_target.clone()));
+        }
+        if source.starts_with(target) || target.starts_with(source) {
+            errors.push(LinkGraphError::SelfLoop(raw_target.clone()));
+        }
+        if by_target[target.as_path()][0] != idx {
+            errors.push(LinkGraphError::SameTarget(raw_target.clone()));
+        }
+        if let Some(outer) = parent_link(&by_target, target) {
+            // The link is written inside the source of its parent link
+            let (outer_source, outer_target) = &paths[outer];
+            let written_at = outer_source.join(target.strip_prefix(outer_target).unwrap());
+            if written_at.starts_with(&dotfiles) {
+                errors.push(LinkGraphError::NestedTarget {
+                    outer: links[outer].1.clone(),
+                    inner: raw_target.clone(),
+                });
+            }
+        }
+    }
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    // The links each link depends on, the ones whose target contains its source
+    // and its parent link, and the links depending on each link
+    let deps = paths
+        .iter()
+        .enumerate()
+        .map(|(idx, (source, target))| {
+            let mut deps = source
+                .ancestors()
+                .filter_map(|ancestor| by_target.get(ancestor))
+                .flatten()
+                .copied()
+                .filter(|dep| *dep != idx)
+                .collect::<Vec<_>>();
+            deps.extend(parent_link(&by_target, target).filter(|dep| !deps.contains(dep)));
+            deps
+        })
+        .collect::<Vec<_>>();
+    let mut dependents = vec![vec![]; links.len()];
+    for (idx, deps) in deps.iter().enumerate() {
+        for dep in deps {
+            dependents[*dep].push(idx);
+        }
+    }
+
+    // Kahn's algorithm, taking the first ready link to keep the declared order as much as possible
+    let mut waiting = deps.iter().map(|deps| deps.len()).collect::<Vec<_>>();
+    let mut ready = (0..links.len())
+        .filter(|idx| waiting[*idx] == 0)
+        .map(Reverse)
+        .collect::<BinaryHeap<_>>();
+    let mut done = vec![false; links.len()];
+    let mut ordered = Vec::with_capacity(links.len());
+    while let Some(Reverse(idx)) = ready.pop() {
+        done[idx] = true;
+        ordered.push(links[idx].clone());
+        for dependent in dependents[idx].iter() {
+            waiting[*dependent] -= 1;
+            if waiting[*dependent] == 0 {
+                ready.push(Reverse(*dependent));
+            }
+        }
+    }
+    if ordered.len() < links.len() {
+        let cycle = find_cycle(&deps, &done)
+            .into_iter()
+            .map(|idx| links[idx].1.clone())
+            .collect();
+        return Err(vec![LinkGraphError::Cycle(cycle)]);
+    }
+
+    Ok(ordered)
+}
+
+/// The link whose target is the nearest ancestor of `target`
+fn parent_link(by_target: &HashMap<&Path, Vec<usize>>, target: &Path) -> Option<usize> {
+    target
+        .ancestors()
+        .skip(1)
+        .find_map(|ancestor| by_target.get(ancestor))
+        .map(|links| links[0])
+}
+
+/// Follow the dependencies of the links which are not done until one comes back
+fn find_cycle(deps: &[Vec<usize>], done: &[bool]) -> Vec<usize> {
+    let mut path = vec![done.iter().position(|done| !done).unwrap()];
+    loop {
+        let last = *path.last().unwrap();
+        let next = *deps[last].iter().find(|dep| !done[**dep]).unwrap();
+        if let Some(start) = path.iter().position(|idx| *idx == next) {
+            return path.split_off(start);
+        }
+        path.push(next);
+    }
+}
+
+/// `path` without `.` and `..`, without touching the filesystem
+fn normalize(path: &Path) -> PathBuf {
+    let mut normal = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normal.pop();
+            }
+            component => normal.push(component),
+        }
+    }
+    normal
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn link(source: &str, target: &str) -> (String, String) {
+        (source.to_string(), target.to_string())
+    }
+
+    fn order(links: &Links) -> Result<Links, Vec<LinkGraphError>> {
+        order_links(links, Path::new("/dotfiles"))
+    }
+
+    #[test]
+    fn it_should_keep_independent_links_in_order() {
+        let links = vec![
+            link("/dotfiles/zsh/.zshrc", "/home/.zshrc"),
+            link("/dotfiles/nvim", "/home/.config/nvim"),
+            link("/dotfiles/alacritty", "/home/.config/alacritty"),
+        ];
+
+        assert_eq!(order(&links), Ok(links));
+    }
+
+    #[test]
+    fn it_should_order_parent_links_first() {
+        let links = vec![
+            link("/home/.lua/plugins", "/home/.plugins"),
+            link("/home/.config/nvim/lua", "/home/.lua"),
+            link("/dotfiles/nvim", "/home/.config/nvim"),
+        ];
+
+        assert_eq!(
+            order(&links),
+            Ok(vec![links[2].clone(), links[1].clone(), links[0].clone()])
+        );
+    }
+
+    #[test]
+    fn it_should_order_nested_targets_after_their_parent() {
+        let links = vec![
+            link("/dotfiles/app/cache.toml", "/home/.cache/app/cache.toml"),
+            link("/dotfiles/app.toml", "/home/.cache/app.toml"),
+            link("/mnt/cache", "/home/.cache"),
+        ];
+
+        assert_eq!(
+            order(&links),
+            Ok(vec![links[2].clone(), links[0].clone(), links[1].clone()])
+        );
+    }
+
+    #[test]
+    fn it_should_detect_targets_inside_the_dotfiles() {
+        let links = vec![link("/dotfiles/a", "/dotfiles/../dotfiles/b")];
+
+        assert_eq!(
+            order(&links),
+            Err(vec![LinkGraphError::TargetInsideDotfiles(
+                "/dotfiles/../dotfiles/b".to_string()
+            )])
+        );
+    }
+
+    #[test]
+    fn it_should_detect_shared_and_nested_targets() {
+        let links = vec![
+            link("/dotfiles/nvim", "/home/.config/nvim"),
+            link("/dotfiles/init.lua", "/home/.config/nvim/init.lua"),
+            link("/dotfiles/nvim2", "/home/.config/nvim"),
+        ];
+
+        assert_eq!(
+            order(&links),
+            Err(vec![
+                LinkGraphError::NestedTarget {
+                    outer: "/home/.config/nvim".to_string(),
+                    inner: "/home/.config/nvim/init.lua".to_string(),
+                },
+                LinkGraphError::SameTarget("/home/.config/nvim".to_string()),
+            ])
+        );
+    }
+
+    #[test]
+    fn it_should_detect_sources_overlapping_their_target() {
+        let links = vec![
+            link("/home/.config", "/home/.config"),
+            link("/home/.local/share/x", "/home/.local"),
+        ];
+
+        assert_eq!(
+            order(&links),
+            Err(vec![
+                LinkGraphError::SelfLoop("/home/.config".to_string()),
+                LinkGraphError::SelfLoop("/home/.local".to_string()),
+            ])
+        );
+    }
+
+    #[test]
+    fn it_should_detect_cycles() {
+        let links = vec![
+            link("/dotfiles/zsh/.zshrc", "/home/.zshrc"),
+            link("/home/b/x", "/home/a"),
+            link("/home/c/x", "/home/b"),
+            link("/home/a/x", "/home/c"),
+        ];
+
+        assert_eq!(
+            order(&links),
+            Err(vec![LinkGraphError::Cycle(vec![
+                "/home/a".to_string(),
+                "/home/b".to_string(),
+                "/home/c".to_string(),
+            ])])
+        );
+    }
+}
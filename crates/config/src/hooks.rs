@@ -0,0 +1,51 @@
+use serde::Deserialize;
+
+/// Shell commands to run around linking
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct Hooks {
+    /// Commands to run before any link is created
+    pub pre_link: Vec<String>,
+    /// Commands to run after the links are created
+    pub post_link: Vec<String>,
+}
+
+impl Hooks {
+    pub fn is_empty(&self) -> bool {
+        self.pre_link.is_empty() && self.post_link.is_empty()
+    }
+}
+
+/// What to do when a hook exits with a non-zero status or times out
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FailurePolicy {
+    /// Stop running hooks, and skip linking if it happens before linking
+    #[default]
+    Abort,
+    /// Report the failure and keep going
+    Continue,
+}
+
+/// The global `[hooks]` table
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct HooksConfig {
+    #[serde(flatten)]
+    pub hooks: Hooks,
+
+    /// Seconds each hook is allowed to run before being killed
+    pub timeout: u64,
+
+    pub on_failure: FailurePolicy,
+}
+
+impl Default for HooksConfig {
+    fn default() -> Self {
+        HooksConfig {
+            hooks: Hooks::default(),
+            timeout: 60,
+            on_failure: FailurePolicy::default(),
+        }
+    }
+}
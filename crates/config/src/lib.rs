@@ -1,6 +1,14 @@
 mod adapter;
 mod config_struct;
+mod editor;
 mod error;
+mod graph;
+mod hooks;
 mod link_transform;
 
 pub use config_struct::*;
+pub use editor::{ConfigEditor, Section};
+pub use error::*;
+pub use graph::order_links;
+pub use hooks::*;
+pub use link_transform::resolve_target;
@@ -1,15 +1,31 @@
 use std::{
+    collections::BTreeMap,
     env::{self, current_dir},
     path::Path,
 };
 
-use crate::Links;
+use crate::{LinkOptions, Links};
 
 pub fn link_transform(links: Links) -> Links {
     let transformer = get_link_transformer();
     links.iter().map(transformer).collect()
 }
 
+pub fn link_options_transform(
+    options: BTreeMap<String, LinkOptions>,
+) -> BTreeMap<String, LinkOptions> {
+    let pwd = current_dir().unwrap();
+    options
+        .into_iter()
+        .map(|(target, options)| (handle_target(&target, &pwd), options))
+        .collect()
+}
+
+/// Resolve a target as written in the config file
+pub fn resolve_target(target: &str) -> String {
+    handle_path_placeholder(target)
+}
+
 fn get_link_transformer() -> impl Fn(&(String, String)) -> (String, String) {
     let pwd = current_dir().unwrap();
     move |(source, target)| (handle_source(source, &pwd), handle_target(target, &pwd))
@@ -25,12 +41,10 @@ fn handle_target(target: &str, _pwd: &Path) -> String {
 
 #[inline]
 fn handle_path_placeholder(path: &str) -> String {
-    if path.starts_with('$') {
-        if path.starts_with("$HOME") {
-            let mut res = env::var("HOME").unwrap();
-            res.push_str(path.strip_prefix("$HOME").unwrap());
-            return res;
-        }
+    if path.starts_with("$HOME") {
+        let mut res = env::var("HOME").unwrap();
+        res.push_str(path.strip_prefix("$HOME").unwrap());
+        return res;
     }
 
     path.to_string()
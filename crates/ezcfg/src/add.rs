@@ -0,0 +1,243 @@
+use std::{
+    env::{self, current_dir},
+    fs::symlink_metadata,
+    iter::once,
+    path::{Component, Path, PathBuf},
+    process::exit,
+};
+
+use clap::ArgMatches;
+use ezcfg_cli::{error, info};
+use ezcfg_config::{resolve_target, Config, ConfigEditor, Section};
+use ezcfg_linker::{FsBackend, LinkBackend};
+
+use crate::{
+    adopt::config_target, link_all_with_config::LinkFlags, link_with_hooks,
+    read_config::read_config, validate_config::validate_config,
+};
+
+pub fn add(matches: &ArgMatches) -> Result<(), ()> {
+    let config = read_config().ok_or(())?;
+    let dotfiles = current_dir().unwrap();
+    let source = matches.get_one::<String>("source").unwrap();
+    let target = matches.get_one::<String>("target").unwrap();
+    let section = matches
+        .get_one::<String>("section")
+        .map(|section| Section::try_from(section.as_str()).unwrap());
+    let home = env::var("HOME").ok().map(PathBuf::from);
+
+    let (source, target) = add_to_config(
+        &config.path,
+        &dotfiles,
+        source,
+        target,
+        section,
+        home.as_deref(),
+    )?;
+
+    if !matches.get_flag("link") {
+        return Ok(());
+    }
+
+    // The new link is checked against the others, but only it gets linked
+    let link = (source.clone(), target.clone());
+    let links = config
+        .links
+        .iter()
+        .cloned()
+        .chain(once(link.clone()))
+        .collect();
+    let config = validate_config(Config { links, ..config }).ok_or(())?;
+    let config = Config {
+        links: config.links.into_iter().filter(|l| *l == link).collect(),
+        ..config
+    };
+    let flags = LinkFlags {
+        catch_interrupts: true,
+        ..Default::default()
+    };
+    if let Err(code) = link_with_hooks(&config, false, flags) {
+        exit(code);
+    }
+
+    match FsBackend.is_linked(&source, &target) {
+        true => Ok(()),
+        false => Err(()),
+    }
+}
+
+/// Append the link from `source` to `target` to the config file at `config_path`
+///
+/// The source must exist inside `dotfiles` and the target must not be declared
+/// in the section yet. The resolved link is returned.
+pub fn add_to_config(
+    config_path: &Path,
+    dotfiles: &Path,
+    source: &str,
+    target: &str,
+    section: Option<Section>,
+    home: Option<&Path>,
+) -> Result<(String, String), ()> {
+    let source_path = dotfiles.join(source);
+    let rel = match source_path.strip_prefix(dotfiles) {
+        Ok(rel) if rel.components().any(|c| !matches!(c, Component::Normal(_))) => {
+            let msg = format!(
+                "Expected a plain relative path inside the dotfiles: {:?}",
+                rel
+            );
+            error!(msg);
+            return Err(());
+        }
+        Ok(rel) if symlink_metadata(&source_path).is_ok() => rel,
+        Ok(_) => {
+            let msg = format!("Source not found: {:?}", source_path);
+            error!(msg);
+            return Err(());
+        }
+        Err(_) => {
+            let msg = format!("Source is not inside the dotfiles: {:?}", source_path);
+            error!(msg);
+            return Err(());
+        }
+    };
+
+    let target = match Path::new(target).is_absolute() {
+        true => config_target(Path::new(target), home),
+        false => target.to_string(),
+    };
+    let resolved_target = resolve_target(&target);
+
+    let mut editor = ConfigEditor::open(config_path).map_err(|e| {
+        let msg = format!("Failed to edit config file: {:?}", config_path);
+        let raw_err = format!("{:?}", e);
+        error!(msg, raw_err);
+    })?;
+    let section = section.unwrap_or_else(|| editor.active_section());
+
+    if editor
+        .links(section)
+        .iter()
+        .any(|(_, declared)| resolve_target(declared) == resolved_target)
+    {
+        let msg = format!("Target already declared in section {}: {}", section, target);
+        error!(msg);
+        return Err(());
+    }
+
+    editor
+        .add_link(section, rel.to_str().unwrap(), &target)
+        .and_then(|_| editor.save())
+        .map_err(|e| {
+            let msg = format!("Failed to add link to section: {}", section);
+            let raw_err = format!("{:?}", e);
+            error!(msg, raw_err);
+        })?;
+
+    let msg = format!(
+        "Added to section {}: {} -> {}",
+        section,
+        rel.display(),
+        target
+    );
+    info!(msg);
+
+    Ok((source_path.to_str().unwrap().to_string(), resolved_target))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::read_to_string;
+
+    use assert_fs::prelude::{FileTouch, FileWriteStr, PathChild};
+    use ezcfg_test::test_in_temp_dir;
+
+    use super::*;
+
+    const CONFIG: &str = "links = [\n    # shell\n    [\"zsh/.zshrc\", \"/home/.zshrc\"],\n]\n";
+
+    test_in_temp_dir!(it_should_add_link_to_section, |temp_dir| {
+        let dotfiles = temp_dir.child("dotfiles");
+        let config = dotfiles.child(".ezcfg.toml");
+        config.write_str(CONFIG).unwrap();
+        dotfiles.child("git/.gitconfig").touch().unwrap();
+        let home = temp_dir.child("home");
+
+        let (source, target) = add_to_config(
+            config.path(),
+            dotfiles.path(),
+            "git/.gitconfig",
+            home.child(".gitconfig").path().to_str().unwrap(),
+            Some(Section::Windows),
+            Some(home.path()),
+        )
+        .unwrap();
+
+        assert_eq!(
+            source,
+            dotfiles.child("git/.gitconfig").path().to_str().unwrap()
+        );
+        assert_eq!(target, resolve_target("$HOME/.gitconfig"));
+        assert_eq!(
+            read_to_string(config.path()).unwrap(),
+            format!(
+                "{}windows = [\n    [\"git/.gitconfig\", \"$HOME/.gitconfig\"],\n]\n",
+                CONFIG
+            )
+        );
+    });
+
+    test_in_temp_dir!(
+        it_should_accept_absolute_source_inside_dotfiles,
+        |temp_dir| {
+            let dotfiles = temp_dir.child("dotfiles");
+            let config = dotfiles.child(".ezcfg.toml");
+            config.write_str("links = []\n").unwrap();
+            let source = dotfiles.child("tmux.conf");
+            source.touch().unwrap();
+
+            add_to_config(
+                config.path(),
+                dotfiles.path(),
+                source.path().to_str().unwrap(),
+                "/etc/tmux.conf",
+                Some(Section::Links),
+                None,
+            )
+            .unwrap();
+
+            assert_eq!(
+                read_to_string(config.path()).unwrap(),
+                "links = [\n    [\"tmux.conf\", \"/etc/tmux.conf\"],\n]\n"
+            );
+        }
+    );
+
+    test_in_temp_dir!(it_should_refuse_invalid_links, |temp_dir| {
+        let dotfiles = temp_dir.child("dotfiles");
+        let config = dotfiles.child(".ezcfg.toml");
+        config.write_str(CONFIG).unwrap();
+        dotfiles.child("zsh/.zshrc").touch().unwrap();
+        temp_dir.child("outside").touch().unwrap();
+
+        let add = |source: &str, target: &str| {
+            add_to_config(
+                config.path(),
+                dotfiles.path(),
+                source,
+                target,
+                Some(Section::Links),
+                None,
+            )
+        };
+
+        assert!(add("missing", "/home/.missing").is_err());
+        assert!(add(
+            temp_dir.child("outside").path().to_str().unwrap(),
+            "/home/.out"
+        )
+        .is_err());
+        assert!(add("zsh/../zsh/.zshrc", "/home/.zsh").is_err());
+        assert!(add("zsh/.zshrc", "/home/.zshrc").is_err());
+        assert_eq!(read_to_string(config.path()).unwrap(), CONFIG);
+    });
+}
@@ -0,0 +1,289 @@
+use std::{
+    env::{self, current_dir},
+    fs::{copy, create_dir_all, read_link, remove_dir_all, remove_file, rename, symlink_metadata},
+    io::{Error as IoError, ErrorKind as IoErrorKind},
+    os::unix::fs::symlink,
+    path::{Component, Path, PathBuf},
+};
+
+use clap::ArgMatches;
+use ezcfg_cli::{error, info, warn};
+use ezcfg_config::ConfigEditor;
+use ezcfg_linker::{FsBackend, LinkBackend, Linker};
+use ezcfg_state::{LinkMode, LinkRecord, State};
+use walkdir::WalkDir;
+
+use crate::read_config::read_config;
+
+pub fn adopt(matches: &ArgMatches) -> Result<(), ()> {
+    let config = read_config().ok_or(())?;
+    let dotfiles = current_dir().unwrap();
+    let path = dotfiles.join(matches.get_one::<String>("path").unwrap());
+    let rel = matches.get_one::<String>("as").map(PathBuf::from);
+    let home = env::var("HOME").ok().map(PathBuf::from);
+
+    let (source, target) = adopt_path(
+        &config.path,
+        &dotfiles,
+        &path,
+        rel.as_deref(),
+        home.as_deref(),
+    )?;
+
+    let state_path = State::default_path();
+    let res = State::load(&state_path).and_then(|mut state| {
+        state.record(LinkRecord::new(
+            &source,
+            &target,
+            LinkMode::Symlink,
+            &config.path,
+        ));
+        state.save(&state_path)
+    });
+    if let Err(e) = res {
+        let msg = format!("Failed to write state file: {:?}", state_path);
+        let raw_err = format!("{:?}", e);
+        warn!(msg, raw_err);
+    }
+
+    Ok(())
+}
+
+/// Move `path` into `dotfiles`, link it back and declare the link in the config file
+///
+/// The target is written with the `$HOME` placeholder when it lives under `home`.
+/// Every step is undone if a later one fails, and the created link is returned.
+pub fn adopt_path(
+    config_path: &Path,
+    dotfiles: &Path,
+    path: &Path,
+    rel: Option<&Path>,
+    home: Option<&Path>,
+) -> Result<(String, String), ()> {
+    match symlink_metadata(path) {
+        Ok(metadata) if metadata.file_type().is_symlink() => {
+            let msg = format!("Refused to adopt a soft link: {:?}", path);
+            error!(msg);
+            return Err(());
+        }
+        Ok(_) => {}
+        Err(e) => {
+            let msg = format!("Failed to adopt: {:?}", path);
+            let raw_err = format!("{:?}", e);
+            error!(msg, raw_err);
+            return Err(());
+        }
+    }
+    if path.starts_with(dotfiles) {
+        let msg = format!("Already inside the dotfiles: {:?}", path);
+        error!(msg);
+        return Err(());
+    }
+
+    let rel = match rel {
+        Some(rel) => rel.to_path_buf(),
+        None => PathBuf::from(path.file_name().unwrap()),
+    };
+    if rel.components().any(|c| !matches!(c, Component::Normal(_))) {
+        let msg = format!(
+            "Expected a plain relative path inside the dotfiles: {:?}",
+            rel
+        );
+        error!(msg);
+        return Err(());
+    }
+    let dest = dotfiles.join(&rel);
+    if symlink_metadata(&dest).is_ok() {
+        let msg = format!("Already exists in the dotfiles: {:?}", dest);
+        error!(msg);
+        return Err(());
+    }
+
+    let target = config_target(path, home);
+    let mut editor = ConfigEditor::open(config_path).map_err(|e| {
+        let msg = format!("Failed to edit config file: {:?}", config_path);
+        let raw_err = format!("{:?}", e);
+        error!(msg, raw_err);
+    })?;
+    let section = editor.active_section();
+    editor
+        .add_link(section, rel.to_str().unwrap(), &target)
+        .map_err(|e| {
+            let msg = format!("Failed to add link to section: {}", section);
+            let raw_err = format!("{:?}", e);
+            error!(msg, raw_err);
+        })?;
+
+    if let Err(e) = dest
+        .parent()
+        .map_or(Ok(()), create_dir_all)
+        .and_then(|_| move_path(path, &dest))
+    {
+        let msg = format!("Failed to move {:?} to {:?}", path, dest);
+        let raw_err = format!("{:?}", e);
+        error!(msg, raw_err);
+        return Err(());
+    }
+
+    let source = dest.to_str().unwrap().to_string();
+    let target = path.to_str().unwrap().to_string();
+    Linker::new().link_all(&[(source.clone(), target.clone())]);
+
+    if !FsBackend.is_linked(&source, &target) {
+        move_back(&dest, path);
+        return Err(());
+    }
+    if let Err(e) = editor.save() {
+        let msg = format!("Failed to write config file: {:?}", config_path);
+        let raw_err = format!("{:?}", e);
+        error!(msg, raw_err);
+        let _ = FsBackend.remove(&target);
+        move_back(&dest, path);
+        return Err(());
+    }
+
+    let msg = format!("Adopted {:?} into section: {}", path, section);
+    info!(msg);
+    Ok((source, target))
+}
+
+/// Write `path` as a target of the config file, using `$HOME` when it is under `home`
+pub fn config_target(path: &Path, home: Option<&Path>) -> String {
+    match home.and_then(|home| path.strip_prefix(home).ok()) {
+        Some(under_home) => format!("$HOME/{}", under_home.to_str().unwrap()),
+        None => path.to_str().unwrap().to_string(),
+    }
+}
+
+fn move_back(dest: &Path, path: &Path) {
+    if let Err(e) = move_path(dest, path) {
+        let msg = format!("Failed to move {:?} back to {:?}", dest, path);
+        let raw_err = format!("{:?}", e);
+        warn!(msg, raw_err);
+    }
+}
+
+/// Rename `from` to `to`, falling back to copy and remove across filesystems
+fn move_path(from: &Path, to: &Path) -> Result<(), IoError> {
+    match rename(from, to) {
+        Err(e) if e.kind() == IoErrorKind::CrossesDevices => {
+            copy_recursively(from, to)?;
+            match symlink_metadata(from)?.is_dir() {
+                true => remove_dir_all(from),
+                false => remove_file(from),
+            }
+        }
+        res => res,
+    }
+}
+
+fn copy_recursively(from: &Path, to: &Path) -> Result<(), IoError> {
+    for entry in WalkDir::new(from).follow_links(false) {
+        let entry = entry.map_err(IoError::from)?;
+        let dest = to.join(entry.path().strip_prefix(from).unwrap());
+        let file_type = entry.file_type();
+
+        if file_type.is_dir() {
+            create_dir_all(&dest)?;
+        } else if file_type.is_symlink() {
+            symlink(read_link(entry.path())?, &dest)?;
+        } else {
+            copy(entry.path(), &dest)?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::read_to_string;
+
+    use assert_fs::prelude::{FileWriteStr, PathChild, PathCreateDir};
+    use ezcfg_test::test_in_temp_dir;
+
+    use super::*;
+
+    const CONFIG: &str = "# my dotfiles\nlinks = [\n    [\"zsh/.zshrc\", \"$HOME/.zshrc\"],\n]\n";
+
+    test_in_temp_dir!(it_should_adopt_file_under_home, |temp_dir| {
+        let dotfiles = temp_dir.child("dotfiles");
+        let config = dotfiles.child(".ezcfg.toml");
+        config.write_str(CONFIG).unwrap();
+        let home = temp_dir.child("home");
+        let tmux = home.child(".tmux.conf");
+        tmux.write_str("set -g mouse on").unwrap();
+
+        let (source, target) = adopt_path(
+            config.path(),
+            dotfiles.path(),
+            tmux.path(),
+            Some(Path::new("tmux/.tmux.conf")),
+            Some(home.path()),
+        )
+        .unwrap();
+
+        let dest = dotfiles.child("tmux/.tmux.conf");
+        assert_eq!(source, dest.path().to_str().unwrap());
+        assert_eq!(target, tmux.path().to_str().unwrap());
+        assert_eq!(read_link(tmux.path()).unwrap(), dest.path());
+        assert_eq!(read_to_string(dest.path()).unwrap(), "set -g mouse on");
+        assert_eq!(
+            read_to_string(config.path()).unwrap(),
+            "# my dotfiles\nlinks = [\n    [\"zsh/.zshrc\", \"$HOME/.zshrc\"],\n    [\"tmux/.tmux.conf\", \"$HOME/.tmux.conf\"],\n]\n"
+        );
+    });
+
+    test_in_temp_dir!(it_should_adopt_dir_outside_home, |temp_dir| {
+        let dotfiles = temp_dir.child("dotfiles");
+        let config = dotfiles.child(".ezcfg.toml");
+        config.write_str("links = []\n").unwrap();
+        let nvim = temp_dir.child("elsewhere/nvim");
+        nvim.create_dir_all().unwrap();
+        nvim.child("init.lua").write_str("").unwrap();
+
+        adopt_path(config.path(), dotfiles.path(), nvim.path(), None, None).unwrap();
+
+        assert!(dotfiles.child("nvim/init.lua").path().is_file());
+        assert!(FsBackend.is_linked(
+            dotfiles.child("nvim").path().to_str().unwrap(),
+            nvim.path().to_str().unwrap()
+        ));
+        assert!(read_to_string(config.path())
+            .unwrap()
+            .contains(&format!("[\"nvim\", {:?}]", nvim.path().to_str().unwrap())));
+    });
+
+    test_in_temp_dir!(it_should_not_touch_anything_when_dest_exists, |temp_dir| {
+        let dotfiles = temp_dir.child("dotfiles");
+        let config = dotfiles.child(".ezcfg.toml");
+        config.write_str(CONFIG).unwrap();
+        dotfiles.child(".zshrc").write_str("old").unwrap();
+        let zshrc = temp_dir.child("home/.zshrc");
+        zshrc.write_str("new").unwrap();
+
+        assert!(adopt_path(config.path(), dotfiles.path(), zshrc.path(), None, None).is_err());
+
+        assert_eq!(read_to_string(zshrc.path()).unwrap(), "new");
+        assert_eq!(read_to_string(config.path()).unwrap(), CONFIG);
+    });
+
+    test_in_temp_dir!(it_should_refuse_to_escape_dotfiles, |temp_dir| {
+        let dotfiles = temp_dir.child("dotfiles");
+        let config = dotfiles.child(".ezcfg.toml");
+        config.write_str(CONFIG).unwrap();
+        let zshrc = temp_dir.child("home/.zshrc");
+        zshrc.write_str("").unwrap();
+
+        let res = adopt_path(
+            config.path(),
+            dotfiles.path(),
+            zshrc.path(),
+            Some(Path::new("../.zshrc")),
+            None,
+        );
+
+        assert!(res.is_err());
+        assert!(zshrc.path().is_file());
+    });
+}
@@ -0,0 +1,284 @@
+use std::{
+    collections::BTreeMap,
+    fmt::{self, Display, Formatter},
+    fs::{read, symlink_metadata},
+    path::{Path, PathBuf},
+};
+
+use clap::ArgMatches;
+use ezcfg_cli::info;
+use ezcfg_config::{resolve_target, Config};
+use ezcfg_linker::{Entry, FsBackend, LinkBackend};
+use similar::TextDiff;
+use walkdir::WalkDir;
+
+use crate::read_config::read_config;
+
+pub fn diff(matches: &ArgMatches) -> Result<(), ()> {
+    let config = read_config().ok_or(())?;
+    let wanted = matches.get_many::<String>("target").map(|targets| {
+        targets
+            .map(|target| resolve_target(target))
+            .collect::<Vec<_>>()
+    });
+
+    let conflicts = conflicts(&config, &FsBackend)
+        .into_iter()
+        .filter(|(_, target)| match &wanted {
+            Some(wanted) => wanted.contains(target),
+            None => true,
+        })
+        .collect::<Vec<_>>();
+    if conflicts.is_empty() {
+        info!("No conflicting target found");
+        return Ok(());
+    }
+
+    for (source, target) in conflicts {
+        println!("{}", diff_paths(Path::new(&target), Path::new(&source)));
+    }
+
+    Ok(())
+}
+
+/// The links whose target exists and is not a link to the source yet
+///
+/// Secrets are left out, their source can not be compared with the target.
+pub fn conflicts<B: LinkBackend>(config: &Config, backend: &B) -> Vec<(String, String)> {
+    config
+        .links
+        .iter()
+        .filter(|(source, target)| {
+            !config.is_encrypted(target)
+                && !matches!(backend.inspect(target), Ok(Entry::Missing))
+                && !backend.is_linked(source, target)
+        })
+        .cloned()
+        .collect()
+}
+
+/// Compare what is at `target` with `source`
+///
+/// Files are compared with a unified diff, directories with a summary
+/// of the files which only exist on one side or differ.
+/// An empty string means there is no difference.
+pub fn diff_paths(target: &Path, source: &Path) -> String {
+    match (Kind::of(target), Kind::of(source)) {
+        (Kind::File, Kind::File) => diff_files(target, source),
+        (Kind::Dir, Kind::Dir) => diff_dirs(target, source),
+        (target_kind, source_kind) => format!(
+            "{} is {}, {} is {}",
+            target.display(),
+            target_kind,
+            source.display(),
+            source_kind
+        ),
+    }
+}
+
+fn diff_files(target: &Path, source: &Path) -> String {
+    let (target_content, source_content) = match (read(target), read(source)) {
+        (Ok(target_content), Ok(source_content)) => (target_content, source_content),
+        _ => {
+            return format!(
+                "Failed to read {} or {}",
+                target.display(),
+                source.display()
+            )
+        }
+    };
+    if target_content == source_content {
+        return String::new();
+    }
+
+    match (
+        String::from_utf8(target_content),
+        String::from_utf8(source_content),
+    ) {
+        (Ok(target_text), Ok(source_text)) => TextDiff::from_lines(&target_text, &source_text)
+            .unified_diff()
+            .header(
+                target.to_str().unwrap_or_default(),
+                source.to_str().unwrap_or_default(),
+            )
+            .to_string(),
+        _ => format!(
+            "Binary files {} and {} differ",
+            target.display(),
+            source.display()
+        ),
+    }
+}
+
+fn diff_dirs(target: &Path, source: &Path) -> String {
+    let target_entries = walk(target);
+    let source_entries = walk(source);
+
+    let mut lines = vec![];
+    for (rel, kind) in target_entries.iter() {
+        match source_entries.get(rel) {
+            None => lines.push(format!("only in {}: {}", target.display(), rel.display())),
+            Some(source_kind) if source_kind != kind => {
+                lines.push(format!(
+                    "{} in {}, {} in {}",
+                    kind,
+                    target.display(),
+                    source_kind,
+                    source.display()
+                ));
+            }
+            Some(Kind::File) if !diff_files(&target.join(rel), &source.join(rel)).is_empty() => {
+                lines.push(format!("differs: {}", rel.display()));
+            }
+            Some(_) => {}
+        }
+    }
+    for rel in source_entries.keys() {
+        if !target_entries.contains_key(rel) {
+            lines.push(format!("only in {}: {}", source.display(), rel.display()));
+        }
+    }
+
+    lines.join("\n")
+}
+
+/// Every entry below `dir`, keyed by its path relative to `dir`
+fn walk(dir: &Path) -> BTreeMap<PathBuf, Kind> {
+    WalkDir::new(dir)
+        .min_depth(1)
+        .follow_links(false)
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .map(|entry| {
+            let rel = entry.path().strip_prefix(dir).unwrap().to_path_buf();
+            (rel, Kind::of(entry.path()))
+        })
+        .collect()
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Kind {
+    Missing,
+    File,
+    Dir,
+    Symlink,
+}
+
+impl Kind {
+    fn of(path: &Path) -> Self {
+        match symlink_metadata(path) {
+            Ok(metadata) if metadata.file_type().is_symlink() => Kind::Symlink,
+            Ok(metadata) if metadata.is_dir() => Kind::Dir,
+            Ok(_) => Kind::File,
+            Err(_) => Kind::Missing,
+        }
+    }
+}
+
+impl Display for Kind {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let kind = match self {
+            Kind::Missing => "missing",
+            Kind::File => "a file",
+            Kind::Dir => "a directory",
+            Kind::Symlink => "a soft link",
+        };
+        write!(f, "{}", kind)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::os::unix::fs::symlink;
+
+    use assert_fs::prelude::{FileTouch, FileWriteStr, PathChild, PathCreateDir};
+    use ezcfg_test::test_in_temp_dir;
+
+    use super::*;
+
+    test_in_temp_dir!(it_should_show_unified_diff_of_files, |temp_dir| {
+        let target = temp_dir.child("target");
+        let source = temp_dir.child("source");
+        target.write_str("a\nb\nc\n").unwrap();
+        source.write_str("a\nB\nc\n").unwrap();
+
+        assert_eq!(
+            diff_paths(target.path(), source.path()),
+            format!(
+                "--- {}\n+++ {}\n@@ -1,3 +1,3 @@\n a\n-b\n+B\n c\n",
+                target.path().display(),
+                source.path().display()
+            )
+        );
+    });
+
+    test_in_temp_dir!(it_should_show_nothing_for_same_files, |temp_dir| {
+        let target = temp_dir.child("target");
+        let source = temp_dir.child("source");
+        target.write_str("same").unwrap();
+        source.write_str("same").unwrap();
+
+        assert_eq!(diff_paths(target.path(), source.path()), "");
+    });
+
+    test_in_temp_dir!(it_should_summarize_dirs_recursively, |temp_dir| {
+        let target = temp_dir.child("target");
+        let source = temp_dir.child("source");
+        target.child("nested/same").write_str("same").unwrap();
+        source.child("nested/same").write_str("same").unwrap();
+        target.child("nested/changed").write_str("old").unwrap();
+        source.child("nested/changed").write_str("new").unwrap();
+        target.child("old").touch().unwrap();
+        source.child("new").create_dir_all().unwrap();
+
+        assert_eq!(
+            diff_paths(target.path(), source.path()),
+            format!(
+                "differs: nested/changed\nonly in {}: old\nonly in {}: new",
+                target.path().display(),
+                source.path().display()
+            )
+        );
+    });
+
+    test_in_temp_dir!(it_should_report_different_kinds, |temp_dir| {
+        let target = temp_dir.child("target");
+        let source = temp_dir.child("source");
+        target.touch().unwrap();
+        source.create_dir_all().unwrap();
+
+        assert_eq!(
+            diff_paths(target.path(), source.path()),
+            format!(
+                "{} is a file, {} is a directory",
+                target.path().display(),
+                source.path().display()
+            )
+        );
+    });
+
+    test_in_temp_dir!(it_should_find_conflicts_only, |temp_dir| {
+        let source = temp_dir.child("source");
+        source.touch().unwrap();
+        let linked = temp_dir.child("linked");
+        symlink(source.path(), linked.path()).unwrap();
+        let conflict = temp_dir.child("conflict");
+        conflict.touch().unwrap();
+
+        let path =
+            |child: &assert_fs::fixture::ChildPath| child.path().to_str().unwrap().to_string();
+        let config = Config::new(
+            vec![
+                (path(&source), path(&linked)),
+                (path(&source), path(&conflict)),
+                (path(&source), path(&temp_dir.child("missing"))),
+            ],
+            false,
+        );
+
+        assert_eq!(
+            conflicts(&config, &FsBackend),
+            vec![(path(&source), path(&conflict))]
+        );
+    });
+}
@@ -0,0 +1,266 @@
+use std::{
+    env::{self, current_dir},
+    fs::{
+        create_dir_all, read, remove_file, set_permissions, symlink_metadata, write, Permissions,
+    },
+    os::unix::fs::PermissionsExt,
+    path::{Component, Path, PathBuf},
+};
+
+use clap::ArgMatches;
+use ezcfg_cli::{error, info, prompt::ask_secret, warn};
+use ezcfg_config::ConfigEditor;
+use ezcfg_state::{LinkMode, LinkRecord, State};
+
+use crate::{
+    adopt::config_target,
+    read_config::read_config,
+    secret::{encrypt as encrypt_secret, generate_identity, identity_path, load_identity, Key},
+};
+
+pub fn encrypt(matches: &ArgMatches) -> Result<(), ()> {
+    let config = read_config().ok_or(())?;
+    let dotfiles = current_dir().unwrap();
+    let path = dotfiles.join(matches.get_one::<String>("path").unwrap());
+    let rel = matches.get_one::<String>("as").map(PathBuf::from);
+    let home = env::var("HOME").ok().map(PathBuf::from);
+
+    let key = match matches.get_flag("passphrase") {
+        true => {
+            let passphrase = ask_secret("Passphrase: ");
+            if passphrase.is_empty() || passphrase != ask_secret("Confirm passphrase: ") {
+                error!("The passphrase is empty or does not match");
+                return Err(());
+            }
+            Key::Passphrase(passphrase.into())
+        }
+        false => {
+            let identity_path = identity_path();
+            let identity = match identity_path.exists() {
+                true => load_identity(&identity_path)?,
+                false => generate_identity(&identity_path)?,
+            };
+            Key::Identity(Box::new(identity))
+        }
+    };
+
+    let (source, target) = encrypt_path(
+        &config.path,
+        &dotfiles,
+        &path,
+        rel.as_deref(),
+        home.as_deref(),
+        &key,
+    )?;
+
+    let state_path = State::default_path();
+    let res = State::load(&state_path).and_then(|mut state| {
+        state.record(LinkRecord::new(
+            &source,
+            &target,
+            LinkMode::Encrypted,
+            &config.path,
+        ));
+        state.save(&state_path)
+    });
+    if let Err(e) = res {
+        let msg = format!("Failed to write state file: {:?}", state_path);
+        let raw_err = format!("{:?}", e);
+        warn!(msg, raw_err);
+    }
+
+    Ok(())
+}
+
+/// Encrypt the file at `path` into `dotfiles` and declare it as an encrypted link
+///
+/// The file stays where it is as the decrypted target, readable by the owner only.
+/// The created link is returned.
+pub fn encrypt_path(
+    config_path: &Path,
+    dotfiles: &Path,
+    path: &Path,
+    rel: Option<&Path>,
+    home: Option<&Path>,
+    key: &Key,
+) -> Result<(String, String), ()> {
+    match symlink_metadata(path) {
+        Ok(metadata) if metadata.is_file() => {}
+        Ok(_) => {
+            let msg = format!("Expected a regular file: {:?}", path);
+            error!(msg);
+            return Err(());
+        }
+        Err(e) => {
+            let msg = format!("Failed to encrypt: {:?}", path);
+            let raw_err = format!("{:?}", e);
+            error!(msg, raw_err);
+            return Err(());
+        }
+    }
+    if path.starts_with(dotfiles) {
+        let msg = format!(
+            "Refused to encrypt a file inside the dotfiles, it may already be committed: {:?}",
+            path
+        );
+        error!(msg);
+        return Err(());
+    }
+
+    let rel = match rel {
+        Some(rel) => rel.to_path_buf(),
+        None => {
+            let name = path.file_name().unwrap().to_str().unwrap();
+            Path::new("secrets").join(format!("{}.age", name.trim_start_matches('.')))
+        }
+    };
+    if rel.components().any(|c| !matches!(c, Component::Normal(_))) {
+        let msg = format!(
+            "Expected a plain relative path inside the dotfiles: {:?}",
+            rel
+        );
+        error!(msg);
+        return Err(());
+    }
+    let dest = dotfiles.join(&rel);
+    if symlink_metadata(&dest).is_ok() {
+        let msg = format!("Already exists in the dotfiles: {:?}", dest);
+        error!(msg);
+        return Err(());
+    }
+
+    let ciphertext = read(path)
+        .map_err(|e| format!("{:?}", e))
+        .and_then(|plaintext| encrypt_secret(&plaintext, key).map_err(|e| format!("{:?}", e)))
+        .map_err(|raw_err| {
+            let msg = format!("Failed to encrypt: {:?}", path);
+            error!(msg, raw_err);
+        })?;
+
+    let target = config_target(path, home);
+    let mut editor = ConfigEditor::open(config_path).map_err(|e| {
+        let msg = format!("Failed to edit config file: {:?}", config_path);
+        let raw_err = format!("{:?}", e);
+        error!(msg, raw_err);
+    })?;
+    let section = editor.active_section();
+    editor
+        .add_encrypted_link(section, rel.to_str().unwrap(), &target)
+        .map_err(|e| {
+            let msg = format!("Failed to add link to section: {}", section);
+            let raw_err = format!("{:?}", e);
+            error!(msg, raw_err);
+        })?;
+
+    if let Err(e) = dest
+        .parent()
+        .map_or(Ok(()), create_dir_all)
+        .and_then(|_| write(&dest, ciphertext))
+    {
+        let msg = format!("Failed to write secret: {:?}", dest);
+        let raw_err = format!("{:?}", e);
+        error!(msg, raw_err);
+        return Err(());
+    }
+    if let Err(e) = editor.save() {
+        let msg = format!("Failed to write config file: {:?}", config_path);
+        let raw_err = format!("{:?}", e);
+        error!(msg, raw_err);
+        let _ = remove_file(&dest);
+        return Err(());
+    }
+
+    if let Err(e) = set_permissions(path, Permissions::from_mode(0o600)) {
+        let msg = format!("Failed to restrict permissions of: {:?}", path);
+        let raw_err = format!("{:?}", e);
+        warn!(msg, raw_err);
+    }
+
+    let msg = format!("Encrypted {:?} to {:?} in section: {}", path, rel, section);
+    info!(msg);
+    Ok((
+        dest.to_str().unwrap().to_string(),
+        path.to_str().unwrap().to_string(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::{metadata, read_to_string};
+
+    use age::x25519::Identity;
+    use assert_fs::prelude::{FileWriteStr, PathChild};
+    use ezcfg_test::test_in_temp_dir;
+
+    use super::*;
+    use crate::secret::decrypt;
+
+    test_in_temp_dir!(it_should_encrypt_file_into_secrets, |temp_dir| {
+        let dotfiles = temp_dir.child("dotfiles");
+        let config = dotfiles.child(".ezcfg.toml");
+        config.write_str("links = []\n").unwrap();
+        let home = temp_dir.child("home");
+        let netrc = home.child(".netrc");
+        netrc.write_str("machine host password hunter2").unwrap();
+        let identity = Identity::generate();
+        let key = Key::Identity(Box::new(identity));
+
+        let (source, target) = encrypt_path(
+            config.path(),
+            dotfiles.path(),
+            netrc.path(),
+            None,
+            Some(home.path()),
+            &key,
+        )
+        .unwrap();
+
+        let dest = dotfiles.child("secrets/netrc.age");
+        assert_eq!(source, dest.path().to_str().unwrap());
+        assert_eq!(target, netrc.path().to_str().unwrap());
+        let ciphertext = read(dest.path()).unwrap();
+        assert!(!String::from_utf8_lossy(&ciphertext).contains("hunter2"));
+        assert_eq!(
+            decrypt(&ciphertext, &key).unwrap(),
+            b"machine host password hunter2"
+        );
+        assert_eq!(
+            metadata(netrc.path()).unwrap().permissions().mode() & 0o777,
+            0o600
+        );
+        assert_eq!(
+            read_to_string(config.path()).unwrap(),
+            "links = [\n    { source = \"secrets/netrc.age\", target = \"$HOME/.netrc\", encrypted = true },\n]\n"
+        );
+
+        // Encrypting it again would overwrite the secret
+        assert!(encrypt_path(
+            config.path(),
+            dotfiles.path(),
+            netrc.path(),
+            None,
+            Some(home.path()),
+            &key,
+        )
+        .is_err());
+    });
+
+    test_in_temp_dir!(it_should_refuse_files_inside_the_dotfiles, |temp_dir| {
+        let config = temp_dir.child(".ezcfg.toml");
+        config.write_str("links = []\n").unwrap();
+        let token = temp_dir.child("token");
+        token.write_str("secret").unwrap();
+        let key = Key::Identity(Box::new(Identity::generate()));
+
+        assert!(encrypt_path(
+            config.path(),
+            temp_dir.path(),
+            token.path(),
+            None,
+            None,
+            &key
+        )
+        .is_err());
+        assert_eq!(read_to_string(config.path()).unwrap(), "links = []\n");
+    });
+}
@@ -0,0 +1,226 @@
+mod runner;
+
+use std::{
+    env::current_dir,
+    fmt::{self, Display, Formatter},
+    path::PathBuf,
+    time::Duration,
+};
+
+use ezcfg_cli::{error, info};
+use ezcfg_config::{Config, FailurePolicy};
+use runner::{run_hook, HookOutput};
+
+use crate::write_state::link_mode;
+
+type HookResult = Result<(), ()>;
+
+#[derive(Debug, Clone, Copy)]
+enum Stage {
+    PreLink,
+    PostLink,
+}
+
+impl Display for Stage {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Stage::PreLink => write!(f, "pre_link"),
+            Stage::PostLink => write!(f, "post_link"),
+        }
+    }
+}
+
+/// Run the global `pre_link` hooks, then the `pre_link` hooks of each link
+pub fn run_pre_link_hooks(config: &Config) -> HookResult {
+    let runner = HookRunner::new(config);
+
+    let mut res = runner.run(Stage::PreLink, None, &config.hooks.hooks.pre_link);
+    for (_, target) in config.links.iter() {
+        if res.is_err() && runner.policy == FailurePolicy::Abort {
+            break;
+        }
+        if let Some(options) = config.link_options(target) {
+            res = res.and(runner.run(Stage::PreLink, Some(target), &options.hooks.pre_link));
+        }
+    }
+
+    res
+}
+
+/// Run the `post_link` hooks of each link in place, then the global `post_link` hooks
+pub fn run_post_link_hooks(config: &Config) -> HookResult {
+    let runner = HookRunner::new(config);
+
+    let mut res = Ok(());
+    for (source, target) in config.links.iter() {
+        if res.is_err() && runner.policy == FailurePolicy::Abort {
+            return res;
+        }
+        if link_mode(config, source, target).is_none() {
+            continue;
+        }
+        if let Some(options) = config.link_options(target) {
+            res = res.and(runner.run(Stage::PostLink, Some(target), &options.hooks.post_link));
+        }
+    }
+    if res.is_err() && runner.policy == FailurePolicy::Abort {
+        return res;
+    }
+
+    res.and(runner.run(Stage::PostLink, None, &config.hooks.hooks.post_link))
+}
+
+struct HookRunner {
+    /// Hooks always run in the root of the dotfiles
+    root: PathBuf,
+    timeout: Duration,
+    policy: FailurePolicy,
+}
+
+impl HookRunner {
+    fn new(config: &Config) -> Self {
+        HookRunner {
+            root: current_dir().unwrap(),
+            timeout: Duration::from_secs(config.hooks.timeout),
+            policy: config.hooks.on_failure,
+        }
+    }
+
+    fn run(&self, stage: Stage, target: Option<&str>, cmds: &[String]) -> HookResult {
+        let mut res = Ok(());
+
+        for cmd in cmds {
+            let name = match target {
+                Some(target) => format!("{} hook of {}", stage, target),
+                None => format!("{} hook", stage),
+            };
+
+            match run_hook(cmd, &self.root, self.timeout) {
+                Ok(output) if output.success() => {
+                    let msg = format!("{} finished in {:.2?}: {}", name, output.elapsed, cmd);
+                    info!(msg);
+                }
+                Ok(output) => {
+                    let msg = format!(
+                        "{} {}: {}",
+                        name,
+                        failure_reason(&output, self.timeout),
+                        cmd
+                    );
+                    let stdout = format!("stdout:{}", indent(&output.stdout));
+                    let stderr = format!("stderr:{}", indent(&output.stderr));
+                    error!(msg, stdout, stderr);
+                    res = Err(());
+                }
+                Err(e) => {
+                    let msg = format!("{} failed to start: {}", name, cmd);
+                    let raw_err = format!("{:?}", e);
+                    error!(msg, raw_err);
+                    res = Err(());
+                }
+            }
+
+            if res.is_err() && self.policy == FailurePolicy::Abort {
+                break;
+            }
+        }
+
+        res
+    }
+}
+
+fn failure_reason(output: &HookOutput, timeout: Duration) -> String {
+    match output.status.map(|status| status.code()) {
+        None => format!("timed out after {:?}", timeout),
+        Some(Some(code)) => format!("exited with status {}", code),
+        Some(None) => "was terminated by a signal".to_string(),
+    }
+}
+
+fn indent(output: &str) -> String {
+    output
+        .lines()
+        .map(|line| format!("\n        {}", line))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::read_to_string;
+
+    use assert_fs::prelude::{FileTouch, PathChild};
+    use ezcfg_config::{Hooks, LinkOptions};
+    use ezcfg_test::test_in_temp_dir;
+
+    use super::*;
+
+    fn config_with_hooks(pre_link: &[&str], post_link: &[&str], policy: FailurePolicy) -> Config {
+        let mut config = Config::default();
+        config.hooks.hooks = Hooks {
+            pre_link: pre_link.iter().map(|cmd| cmd.to_string()).collect(),
+            post_link: post_link.iter().map(|cmd| cmd.to_string()).collect(),
+        };
+        config.hooks.on_failure = policy;
+        config
+    }
+
+    test_in_temp_dir!(it_should_abort_on_first_failure, |temp_dir| {
+        let log = temp_dir.child("log");
+        let log_path = log.path().to_str().unwrap();
+        let config = config_with_hooks(
+            &["false", &format!("echo ran >> {}", log_path)],
+            &[],
+            FailurePolicy::Abort,
+        );
+
+        assert!(run_pre_link_hooks(&config).is_err());
+        assert!(!log.path().exists());
+    });
+
+    test_in_temp_dir!(it_should_continue_after_failure, |temp_dir| {
+        let log = temp_dir.child("log");
+        let log_path = log.path().to_str().unwrap();
+        let config = config_with_hooks(
+            &["false", &format!("echo ran >> {}", log_path)],
+            &[],
+            FailurePolicy::Continue,
+        );
+
+        assert!(run_pre_link_hooks(&config).is_err());
+        assert_eq!(read_to_string(log.path()).unwrap(), "ran\n");
+    });
+
+    test_in_temp_dir!(
+        it_should_only_run_post_link_hooks_of_linked_targets,
+        |temp_dir| {
+            let source = temp_dir.child("source");
+            source.touch().unwrap();
+            let linked = temp_dir.child("linked");
+            std::os::unix::fs::symlink(source.path(), linked.path()).unwrap();
+            let log = temp_dir.child("log");
+            let log_path = log.path().to_str().unwrap();
+
+            let mut config = config_with_hooks(&[], &[], FailurePolicy::Abort);
+            let source_path = source.path().to_str().unwrap().to_string();
+            temp_dir.child("decrypted").touch().unwrap();
+            for name in ["linked", "missing", "decrypted", "encrypted"] {
+                let target = temp_dir.child(name).path().to_str().unwrap().to_string();
+                config.links.push((source_path.clone(), target.clone()));
+                config.link_options.insert(
+                    target,
+                    LinkOptions {
+                        hooks: Hooks {
+                            pre_link: vec![],
+                            post_link: vec![format!("echo {} >> {}", name, log_path)],
+                        },
+                        encrypted: name.ends_with("crypted"),
+                        ..Default::default()
+                    },
+                );
+            }
+
+            assert!(run_post_link_hooks(&config).is_ok());
+            assert_eq!(read_to_string(log.path()).unwrap(), "linked\ndecrypted\n");
+        }
+    );
+}
@@ -0,0 +1,127 @@
+use std::{
+    io::{Error as IoError, Read},
+    os::unix::process::CommandExt,
+    path::Path,
+    process::{Child, Command, ExitStatus, Stdio},
+    thread::{spawn, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use wait_timeout::ChildExt;
+
+/// The result of a hook which has been started
+#[derive(Debug)]
+pub struct HookOutput {
+    /// `None` when the hook was killed after timing out
+    pub status: Option<ExitStatus>,
+    pub stdout: String,
+    pub stderr: String,
+    pub elapsed: Duration,
+}
+
+impl HookOutput {
+    pub fn success(&self) -> bool {
+        self.status.is_some_and(|status| status.success())
+    }
+}
+
+/// Run `cmd` with `sh -c` in `cwd`, capturing its output,
+/// the hook is killed if it runs longer than `timeout`
+pub fn run_hook(cmd: &str, cwd: &Path, timeout: Duration) -> Result<HookOutput, IoError> {
+    let start = Instant::now();
+
+    let mut child = Command::new("sh")
+        .arg("-c")
+        .arg(cmd)
+        .current_dir(cwd)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        // Run in its own process group, so that a timeout kills its children as well
+        .process_group(0)
+        .spawn()?;
+
+    // Drain the pipes in the background,
+    // otherwise a chatty hook would block once the pipe buffer is full
+    let stdout = drain(child.stdout.take());
+    let stderr = drain(child.stderr.take());
+
+    let status = wait_or_kill(&mut child, timeout)?;
+
+    Ok(HookOutput {
+        status,
+        stdout: stdout.join().unwrap_or_default(),
+        stderr: stderr.join().unwrap_or_default(),
+        elapsed: start.elapsed(),
+    })
+}
+
+fn wait_or_kill(child: &mut Child, timeout: Duration) -> Result<Option<ExitStatus>, IoError> {
+    match child.wait_timeout(timeout)? {
+        Some(status) => Ok(Some(status)),
+        None => {
+            // SAFETY: `kill` has no memory safety requirements,
+            // the negative pid targets the process group created for the hook
+            unsafe { libc::kill(-(child.id() as libc::pid_t), libc::SIGKILL) };
+            child.wait()?;
+            Ok(None)
+        }
+    }
+}
+
+fn drain<R: Read + Send + 'static>(pipe: Option<R>) -> JoinHandle<String> {
+    spawn(move || {
+        let mut buf = vec![];
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_end(&mut buf);
+        }
+        String::from_utf8_lossy(&buf).to_string()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env::temp_dir;
+
+    use super::*;
+
+    fn run(cmd: &str) -> HookOutput {
+        run_hook(cmd, &temp_dir(), Duration::from_secs(5)).unwrap()
+    }
+
+    #[test]
+    fn it_should_capture_output() {
+        let output = run("echo out; echo err >&2");
+
+        assert!(output.success());
+        assert_eq!(output.stdout, "out\n");
+        assert_eq!(output.stderr, "err\n");
+    }
+
+    #[test]
+    fn it_should_report_non_zero_exit() {
+        let output = run("exit 3");
+
+        assert!(!output.success());
+        assert_eq!(output.status.unwrap().code(), Some(3));
+    }
+
+    #[test]
+    fn it_should_run_in_the_given_dir() {
+        let output = run("pwd");
+
+        assert_eq!(
+            Path::new(output.stdout.trim()).canonicalize().unwrap(),
+            temp_dir().canonicalize().unwrap()
+        );
+    }
+
+    #[test]
+    fn it_should_kill_hook_after_timeout() {
+        let output = run_hook("sleep 5", &temp_dir(), Duration::from_millis(100)).unwrap();
+
+        assert!(output.status.is_none());
+        assert!(!output.success());
+        assert!(output.elapsed < Duration::from_secs(5));
+    }
+}
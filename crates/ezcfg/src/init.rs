@@ -0,0 +1,192 @@
+use std::{
+    env::{self, current_dir},
+    fs::{create_dir_all, symlink_metadata, write},
+    path::{Path, PathBuf},
+};
+
+use clap::ArgMatches;
+use ezcfg_cli::{error, info, prompt::confirm};
+
+use crate::{adopt::adopt_path, read_config::CONFIG_FILES};
+
+static TEMPLATE: &str = r#"# Each link is a [source, target] pair.
+# The source is relative to the directory ezcfg runs in,
+# and `$HOME` at the start of the target expands to your home directory.
+
+# Replace the files already existing at the targets
+rewrite = false
+
+# Links shared by every OS
+links = [
+    # ["zsh/.zshrc", "$HOME/.zshrc"],
+    # ["nvim", "$HOME/.config/nvim"],
+    # A link written as a table can have its own hooks
+    # { source = "git/.gitconfig", target = "$HOME/.gitconfig", hooks = { post_link = ["git config --list"] } },
+]
+
+# Links of a single OS, which take the place of `links` on that OS
+# linux = []
+# macos = []
+# windows = []
+
+# Commands to run around linking
+# [hooks]
+# pre_link = ["git pull --ff-only"]
+# post_link = []
+# timeout = 60
+# on_failure = "abort"
+"#;
+
+/// Well-known dotfiles under `$HOME`, and where they go inside the dotfiles
+static WELL_KNOWN: [(&str, &str); 7] = [
+    (".zshrc", "zsh/.zshrc"),
+    (".bashrc", "bash/.bashrc"),
+    (".gitconfig", "git/.gitconfig"),
+    (".vimrc", "vim/.vimrc"),
+    (".tmux.conf", "tmux/.tmux.conf"),
+    (".config/nvim", "nvim"),
+    (".config/alacritty", "alacritty"),
+];
+
+pub fn init(matches: &ArgMatches) -> Result<(), ()> {
+    let dotfiles = current_dir().unwrap();
+    let config_file = match matches.get_flag("nested") {
+        true => CONFIG_FILES[1],
+        false => CONFIG_FILES[0],
+    };
+    let config_path = init_config(&dotfiles, config_file)?;
+
+    let msg = format!("Created config file: {:?}", config_path);
+    info!(msg);
+
+    if !matches.get_flag("scan") {
+        return Ok(());
+    }
+
+    let home = PathBuf::from(env::var("HOME").unwrap_or_default());
+    let found = scan_well_known(&home);
+    if found.is_empty() {
+        info!("No well-known dotfile found in $HOME");
+        return Ok(());
+    }
+
+    let mut res = Ok(());
+    for (path, rel) in found {
+        let question = format!("Adopt {} as {}?", path.display(), rel);
+        if confirm(&question)
+            && adopt_path(
+                &config_path,
+                &dotfiles,
+                &path,
+                Some(Path::new(rel)),
+                Some(&home),
+            )
+            .is_err()
+        {
+            res = Err(());
+        }
+    }
+
+    res
+}
+
+/// Write the config template to `config_file` inside `dotfiles`
+///
+/// Nothing is written if any config file already exists there.
+fn init_config(dotfiles: &Path, config_file: &str) -> Result<PathBuf, ()> {
+    if let Some(existing) = CONFIG_FILES
+        .iter()
+        .map(|file| dotfiles.join(file))
+        .find(|path| path.exists())
+    {
+        let msg = format!("Config file already exists: {:?}", existing);
+        error!(msg);
+        return Err(());
+    }
+
+    let config_path = dotfiles.join(config_file);
+    config_path
+        .parent()
+        .map_or(Ok(()), create_dir_all)
+        .and_then(|_| write(&config_path, TEMPLATE))
+        .map_err(|e| {
+            let msg = format!("Failed to write config file: {:?}", config_path);
+            let raw_err = format!("{:?}", e);
+            error!(msg, raw_err);
+        })?;
+
+    Ok(config_path)
+}
+
+/// The well-known dotfiles which exist under `home` and are not soft links yet
+fn scan_well_known(home: &Path) -> Vec<(PathBuf, &'static str)> {
+    WELL_KNOWN
+        .iter()
+        .map(|(name, rel)| (home.join(name), *rel))
+        .filter(|(path, _)| {
+            symlink_metadata(path).is_ok_and(|metadata| !metadata.file_type().is_symlink())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs::read_to_string, os::unix::fs::symlink};
+
+    use assert_fs::prelude::{FileTouch, FileWriteStr, PathChild, PathCreateDir};
+    use ezcfg_config::{Config, ConfigEditor, Section};
+    use ezcfg_test::test_in_temp_dir;
+
+    use super::*;
+
+    test_in_temp_dir!(it_should_write_template, |temp_dir| {
+        let config_path = init_config(temp_dir.path(), CONFIG_FILES[1]).unwrap();
+
+        assert_eq!(config_path, temp_dir.child(".ezcfg/ezcfg.toml").path());
+        assert_eq!(read_to_string(&config_path).unwrap(), TEMPLATE);
+
+        let config = Config::try_from(&config_path).unwrap();
+        assert!(config.links.is_empty());
+        assert!(!config.rewrite);
+    });
+
+    test_in_temp_dir!(it_should_not_overwrite_existing_config, |temp_dir| {
+        let existing = temp_dir.child(".ezcfg/ezcfg.toml");
+        existing.write_str("links = []").unwrap();
+
+        assert!(init_config(temp_dir.path(), CONFIG_FILES[0]).is_err());
+        assert!(!temp_dir.child(".ezcfg.toml").path().exists());
+        assert_eq!(read_to_string(existing.path()).unwrap(), "links = []");
+    });
+
+    #[test]
+    fn it_should_be_editable_template() {
+        let mut editor = ConfigEditor::parse(TEMPLATE).unwrap();
+        editor
+            .add_link(Section::Links, "zsh/.zshrc", "$HOME/.zshrc")
+            .unwrap();
+
+        assert!(editor
+            .to_string()
+            .contains("# { source = \"git/.gitconfig\", target = \"$HOME/.gitconfig\", hooks = { post_link = [\"git config --list\"] } },\n    [\"zsh/.zshrc\", \"$HOME/.zshrc\"],\n]"));
+    }
+
+    test_in_temp_dir!(it_should_find_well_known_dotfiles, |temp_dir| {
+        temp_dir.child(".zshrc").touch().unwrap();
+        temp_dir.child(".config/nvim").create_dir_all().unwrap();
+        temp_dir.child("elsewhere").touch().unwrap();
+        symlink(
+            temp_dir.child("elsewhere").path(),
+            temp_dir.child(".gitconfig").path(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            scan_well_known(temp_dir.path()),
+            vec![
+                (temp_dir.child(".zshrc").path().to_path_buf(), "zsh/.zshrc"),
+                (temp_dir.child(".config/nvim").path().to_path_buf(), "nvim"),
+            ]
+        );
+    });
+}
@@ -0,0 +1,38 @@
+use std::{
+    process::exit,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, OnceLock,
+    },
+};
+
+use ezcfg_cli::warn;
+
+static INTERRUPTED: OnceLock<Arc<AtomicBool>> = OnceLock::new();
+
+/// The flag set by Ctrl-C once [`catch_interrupts`] has been called
+pub fn interrupted() -> Arc<AtomicBool> {
+    INTERRUPTED.get_or_init(Arc::default).clone()
+}
+
+pub fn is_interrupted() -> bool {
+    interrupted().load(Ordering::SeqCst)
+}
+
+/// Let Ctrl-C stop the run between two links, instead of killing it in the middle of one
+///
+/// A second Ctrl-C quits right away, without rolling back `--atomic` runs:
+/// the targets moved aside are then left next to them as `<target>.ezcfg-stash-*`.
+pub fn catch_interrupts() {
+    let interrupted = interrupted();
+    let res = ctrlc::set_handler(move || {
+        if interrupted.swap(true, Ordering::SeqCst) {
+            exit(130);
+        }
+    });
+
+    if let Err(e) = res {
+        let raw_err = format!("{:?}", e);
+        warn!("Failed to catch Ctrl-C", raw_err);
+    }
+}
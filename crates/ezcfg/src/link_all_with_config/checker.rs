@@ -1,62 +1,112 @@
-use std::{
-    fs::{remove_dir_all, remove_file},
-    path::Path,
-};
+use std::path::Path;
 
 use ezcfg_cli::warn;
 use ezcfg_config::Config;
+use ezcfg_linker::{Decision, Entry, LinkBackend, SkipReason};
+
+use super::{guard::Guard, LinkFlags};
+use crate::diff::diff_paths;
+
+type CheckResult = Result<(), Decision>;
+
+pub fn check_path<B: LinkBackend>(
+    config: &Config,
+    backend: &B,
+    guard: Option<&Guard>,
+    source: &str,
+    target: &str,
+    flags: LinkFlags,
+) -> Decision {
+    check_source_exist(backend, source)
+        .and_then(|_| check_rewrite_target(config, backend, guard, source, target, flags))
+        .err()
+        .unwrap_or(Decision::Link)
+}
 
-type CheckResult = Result<(), ()>;
-
-pub fn check_path(config: &Config, source: &str, target: &str) -> CheckResult {
-    let source_path = Path::new(source);
-    let target_path = Path::new(target);
-
-    check_source_exist(source_path)?;
-    check_rewrite_target(target_path, config.rewrite)?;
-
-    Ok(())
+/// Check a target chosen to be overwritten, whose source only has to exist
+pub fn check_overwrite<B: LinkBackend>(backend: &B, source: &str) -> Decision {
+    check_source_exist(backend, source)
+        .err()
+        .unwrap_or(Decision::Replace)
 }
 
 #[inline]
-fn check_source_exist(source: &Path) -> CheckResult {
-    if !source.exists() {
-        let msg = format!("Path dose not exist: {:?}", source);
-        warn!(msg);
-
-        return Err(());
+fn check_source_exist<B: LinkBackend>(backend: &B, source: &str) -> CheckResult {
+    match backend.inspect(source) {
+        Ok(Entry::Missing) => Err(Decision::skip("source does not exist")),
+        Ok(_) => Ok(()),
+        Err(e) => Err(Decision::skip(format!("failed to inspect source, {}", e))),
     }
-    Ok(())
 }
 
-// TODO maybe I should remove the existed target in the reading stage of config file
-// TODO instead of here
 #[inline]
-fn check_rewrite_target(target: &Path, rewrite: bool) -> CheckResult {
-    if !target.exists() {
-        return Ok(());
+fn check_rewrite_target<B: LinkBackend>(
+    config: &Config,
+    backend: &B,
+    guard: Option<&Guard>,
+    source: &str,
+    target: &str,
+    flags: LinkFlags,
+) -> CheckResult {
+    match backend.inspect(target) {
+        Ok(Entry::Missing) => return Ok(()),
+        Ok(Entry::Symlink(dest)) if dest == source => {
+            return Err(Decision::Skip(SkipReason::UpToDate))
+        }
+        Ok(_) => {}
+        // Linking fails the same way, and is reported as such
+        Err(_) => return Ok(()),
     }
 
-    match rewrite {
-        false => Err(()),
-
-        true if target.is_file() || target.is_symlink() => {
-            remove_file(target).unwrap();
-            Ok(())
+    let (source, target) = (Path::new(source), Path::new(target));
+    match config.rewrite {
+        false => {
+            if flags.show_diff {
+                report_diff(source, target);
+            }
+            Err(Decision::skip(
+                "target already exists, set `rewrite = true` to replace it or run `ezcfg diff`",
+            ))
         }
-        true if target.is_dir() => {
-            remove_dir_all(target).unwrap();
-            Ok(())
+
+        // The linker removes the target right before linking,
+        // so the check leaves the filesystem untouched
+        true => {
+            check_safe_to_remove(guard, source, target)?;
+            Err(Decision::Replace)
         }
-        _ => Err(()),
     }
 }
 
+/// Targets are only removed when `guard` allows it, `None` meaning `--force-unsafe`
+#[inline]
+fn check_safe_to_remove(guard: Option<&Guard>, source: &Path, target: &Path) -> CheckResult {
+    let Some(guard) = guard else {
+        return Ok(());
+    };
+    guard.check_removal(source, target).map_err(|reason| {
+        Decision::skip(format!(
+            "refused to replace target, {}, run with `--force-unsafe` to allow it",
+            reason
+        ))
+    })
+}
+
+fn report_diff(source: &Path, target: &Path) {
+    let msg = format!("Target already exists: {:?}", target);
+    let diff = match diff_paths(target, source) {
+        diff if diff.is_empty() => "Same content as the source".to_string(),
+        diff => diff.trim_end().replace('\n', "\n    "),
+    };
+    warn!(msg, diff);
+}
+
 #[cfg(test)]
 mod tests {
-    use std::os::unix::fs::symlink;
+    use std::{fs::read_to_string, os::unix::fs::symlink};
 
-    use assert_fs::prelude::{FileTouch, PathChild, PathCreateDir};
+    use assert_fs::prelude::{FileTouch, FileWriteStr, PathChild, PathCreateDir};
+    use ezcfg_linker::{FsBackend, MemoryBackend};
     use ezcfg_test::test_in_temp_dir;
 
     use super::*;
@@ -68,12 +118,30 @@ mod tests {
         source.touch().unwrap();
 
         let source_path = source.path().to_str().unwrap();
-        assert!(check_path(&config, source_path, "").is_ok());
+        assert!(
+            check_path(
+                &config,
+                &FsBackend,
+                Some(&Guard::new(&config)),
+                source_path,
+                "",
+                LinkFlags::default()
+            ) == Decision::Link
+        );
     });
 
     test_in_temp_dir!(it_should_not_pass_when_source_not_exist, |_| {
         let config = Config::default();
-        assert!(check_path(&config, "not-exist", "").is_err());
+        assert!(
+            check_path(
+                &config,
+                &FsBackend,
+                Some(&Guard::new(&config)),
+                "not-exist",
+                "",
+                LinkFlags::default()
+            ) != Decision::Link
+        );
     });
 
     test_in_temp_dir!(it_should_pass_when_target_not_exist, |temp_dir| {
@@ -83,28 +151,51 @@ mod tests {
         source.touch().unwrap();
 
         let source_path = source.path().to_str().unwrap();
-        assert!(check_path(&config, source_path, "not-exist").is_ok());
+        assert!(
+            check_path(
+                &config,
+                &FsBackend,
+                Some(&Guard::new(&config)),
+                source_path,
+                "not-exist",
+                LinkFlags::default()
+            ) == Decision::Link
+        );
     });
 
     test_in_temp_dir!(
         it_should_pass_when_enable_rewrite_and_target_not_exist,
         |temp_dir| {
-            let mut config = Config::default();
-            config.rewrite = true;
+            let config = Config {
+                rewrite: true,
+                ..Default::default()
+            };
 
             let source = temp_dir.child("source");
             source.touch().unwrap();
 
             let source_path = source.path().to_str().unwrap();
-            assert!(check_path(&config, source_path, "not-exist").is_ok());
+            assert!(
+                check_path(
+                    &config,
+                    &FsBackend,
+                    Some(&Guard::new(&config)),
+                    source_path,
+                    "not-exist",
+                    LinkFlags::default()
+                ) == Decision::Link
+            );
         }
     );
 
     test_in_temp_dir!(
-        it_should_pass_and_delete_target_when_enable_rewrite_and_target_file_exist,
+        it_should_replace_target_when_enable_rewrite_and_target_file_exist,
         |temp_dir| {
-            let mut config = Config::default();
-            config.rewrite = true;
+            let config = Config {
+                rewrite: true,
+                target_roots: vec![temp_dir.path().to_path_buf()],
+                ..Default::default()
+            };
 
             let source = temp_dir.child("source");
             source.touch().unwrap();
@@ -118,17 +209,30 @@ mod tests {
             let source_path = source.path().to_str().unwrap();
             let target_path = target.path().to_str().unwrap();
 
-            assert!(check_path(&config, source_path, target_path).is_ok());
-
-            assert!(!target.exists());
+            assert!(
+                check_path(
+                    &config,
+                    &FsBackend,
+                    Some(&Guard::new(&config)),
+                    source_path,
+                    target_path,
+                    LinkFlags::default()
+                ) == Decision::Replace
+            );
+
+            // The target is only removed by the linker
+            assert!(target.is_file());
         }
     );
 
     test_in_temp_dir!(
-        it_should_pass_and_delete_target_when_enable_rewrite_and_target_dir_exist,
+        it_should_replace_target_when_enable_rewrite_and_target_dir_exist,
         |temp_dir| {
-            let mut config = Config::default();
-            config.rewrite = true;
+            let config = Config {
+                rewrite: true,
+                target_roots: vec![temp_dir.path().to_path_buf()],
+                ..Default::default()
+            };
 
             let source = temp_dir.child("source");
             source.touch().unwrap();
@@ -142,17 +246,30 @@ mod tests {
             let source_path = source.path().to_str().unwrap();
             let target_path = target.path().to_str().unwrap();
 
-            assert!(check_path(&config, source_path, target_path).is_ok());
-
-            assert!(!target.exists());
+            assert!(
+                check_path(
+                    &config,
+                    &FsBackend,
+                    Some(&Guard::new(&config)),
+                    source_path,
+                    target_path,
+                    LinkFlags::default()
+                ) == Decision::Replace
+            );
+
+            // The target is only removed by the linker
+            assert!(target.is_dir());
         }
     );
 
     test_in_temp_dir!(
-        it_should_pass_and_delete_target_when_enable_rewrite_and_target_link_exist,
+        it_should_replace_target_when_enable_rewrite_and_target_link_exist,
         |temp_dir| {
-            let mut config = Config::default();
-            config.rewrite = true;
+            let config = Config {
+                rewrite: true,
+                target_roots: vec![temp_dir.path().to_path_buf()],
+                ..Default::default()
+            };
 
             let source = temp_dir.child("source");
             source.touch().unwrap();
@@ -169,9 +286,163 @@ mod tests {
             let source_path = source.path().to_str().unwrap();
             let target_path = target.path().to_str().unwrap();
 
-            assert!(check_path(&config, source_path, target_path).is_ok());
+            assert!(
+                check_path(
+                    &config,
+                    &FsBackend,
+                    Some(&Guard::new(&config)),
+                    source_path,
+                    target_path,
+                    LinkFlags::default()
+                ) == Decision::Replace
+            );
+
+            // The target is only removed by the linker
+            assert!(target.is_symlink());
+        }
+    );
+
+    test_in_temp_dir!(
+        it_should_not_pass_and_keep_target_when_disable_rewrite_and_target_exist,
+        |temp_dir| {
+            let config = Config::default();
+            let flags = LinkFlags {
+                show_diff: true,
+                ..Default::default()
+            };
+
+            let source = temp_dir.child("source");
+            source.write_str("new").unwrap();
+            let target = temp_dir.child("target");
+            target.write_str("old").unwrap();
+
+            let source_path = source.path().to_str().unwrap();
+            let target_path = target.path().to_str().unwrap();
+
+            assert!(
+                check_path(
+                    &config,
+                    &FsBackend,
+                    Some(&Guard::new(&config)),
+                    source_path,
+                    target_path,
+                    flags
+                ) != Decision::Link
+            );
+
+            assert_eq!(read_to_string(target.path()).unwrap(), "old");
+        }
+    );
+
+    test_in_temp_dir!(
+        it_should_not_pass_and_keep_target_outside_target_roots,
+        |temp_dir| {
+            let config = Config {
+                rewrite: true,
+                target_roots: vec![temp_dir.child("home").path().to_path_buf()],
+                ..Default::default()
+            };
+
+            let source = temp_dir.child("source");
+            source.touch().unwrap();
+            let target = temp_dir.child("target");
+            target.create_dir_all().unwrap();
+
+            let source_path = source.path().to_str().unwrap();
+            let target_path = target.path().to_str().unwrap();
+
+            assert!(
+                check_path(
+                    &config,
+                    &FsBackend,
+                    Some(&Guard::new(&config)),
+                    source_path,
+                    target_path,
+                    LinkFlags::default()
+                ) != Decision::Link
+            );
+            assert!(target.is_dir());
+
+            let flags = LinkFlags {
+                force_unsafe: true,
+                ..Default::default()
+            };
+            assert!(
+                check_path(&config, &FsBackend, None, source_path, target_path, flags)
+                    == Decision::Replace
+            );
+            assert!(target.is_dir());
+        }
+    );
+
+    #[test]
+    fn it_should_replace_dangling_links() {
+        let backend = MemoryBackend::new()
+            .with_file("/dotfiles/source")
+            .with_symlink("/home/target", "/dotfiles/missing");
+        let config = Config {
+            rewrite: true,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            check_path(
+                &config,
+                &backend,
+                None,
+                "/dotfiles/source",
+                "/home/target",
+                LinkFlags::default()
+            ),
+            Decision::Replace
+        );
+        assert_eq!(
+            check_path(
+                &Config::default(),
+                &backend,
+                None,
+                "/dotfiles/source",
+                "/home/target",
+                LinkFlags::default()
+            ),
+            Decision::skip(
+                "target already exists, set `rewrite = true` to replace it or run `ezcfg diff`"
+            )
+        );
+        assert_eq!(
+            check_overwrite(&backend, "/dotfiles/missing"),
+            Decision::skip("source does not exist")
+        );
+    }
+
+    test_in_temp_dir!(
+        it_should_be_up_to_date_when_target_links_to_source,
+        |temp_dir| {
+            let config = Config {
+                rewrite: true,
+                ..Default::default()
+            };
 
-            assert!(!target.exists());
+            let source = temp_dir.child("source");
+            source.touch().unwrap();
+            let target = temp_dir.child("target");
+            symlink(source.path(), target.path()).unwrap();
+
+            let source_path = source.path().to_str().unwrap();
+            let target_path = target.path().to_str().unwrap();
+
+            assert_eq!(
+                check_path(
+                    &config,
+                    &FsBackend,
+                    Some(&Guard::new(&config)),
+                    source_path,
+                    target_path,
+                    LinkFlags::default()
+                ),
+                Decision::Skip(SkipReason::UpToDate)
+            );
+            assert!(target.is_symlink());
         }
     );
 }
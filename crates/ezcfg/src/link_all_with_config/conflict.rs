@@ -0,0 +1,308 @@
+use std::{collections::BTreeMap, io::Error as IoError, path::Path};
+
+use ezcfg_cli::{info, warn};
+use ezcfg_config::Config;
+use ezcfg_linker::{Decision, Entry, LinkBackend};
+
+use super::guard::Guard;
+use crate::diff::{conflicts, diff_paths};
+
+/// What to do with a target which already exists
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resolution {
+    /// Replace the target with the link, it is removed right before linking
+    Overwrite,
+    /// Move the target aside to `<target>.bak`
+    Backup,
+    /// Leave the target alone and do not link it
+    Skip,
+    /// Replace the source with the target
+    Adopt,
+}
+
+static QUESTION: &str =
+    "[o]verwrite, [b]ack up, [s]kip, [d]iff or [a]dopt? Add `!` to apply to all:";
+
+/// Ask how to resolve every conflicting target of `config`, and apply the answers
+///
+/// Each question is answered by `ask`, an empty answer skips the target.
+/// Targets which `guard` protects are neither overwritten nor adopted.
+/// The decisions for the targets which must not go through the usual checks are returned,
+/// the ones to overwrite being left in place until they are linked.
+pub fn resolve_conflicts<B, F>(
+    config: &Config,
+    backend: &B,
+    guard: Option<&Guard>,
+    mut ask: F,
+) -> BTreeMap<String, Decision>
+where
+    B: LinkBackend,
+    F: FnMut(&str) -> String,
+{
+    let mut apply_to_all = None;
+    let mut decisions = BTreeMap::new();
+
+    for (source, target) in conflicts(config, backend) {
+        let resolution = match apply_to_all {
+            Some(resolution) => resolution,
+            None => loop {
+                let answer = ask(&format!("Target already exists: {}\n{} ", target, QUESTION));
+                let (choice, all) = match answer.strip_suffix('!') {
+                    Some(choice) => (choice, true),
+                    None => (answer.as_str(), false),
+                };
+
+                let resolution = match choice {
+                    "o" | "overwrite" => Resolution::Overwrite,
+                    "b" | "backup" => Resolution::Backup,
+                    "s" | "skip" | "" => Resolution::Skip,
+                    "a" | "adopt" => Resolution::Adopt,
+                    "d" | "diff" => {
+                        match diff_paths(Path::new(&target), Path::new(&source)) {
+                            diff if diff.is_empty() => println!("Same content as the source"),
+                            diff => println!("{}", diff.trim_end()),
+                        }
+                        continue;
+                    }
+                    _ => continue,
+                };
+                if all {
+                    apply_to_all = Some(resolution);
+                }
+                break resolution;
+            },
+        };
+
+        let refused = match resolution {
+            Resolution::Overwrite | Resolution::Adopt => guard
+                .map(|guard| guard.check_removal(Path::new(&source), Path::new(&target)))
+                .and_then(|res| res.err()),
+            _ => None,
+        };
+        if let Some(reason) = refused {
+            let msg = format!("Refused to replace target: {}", target);
+            let reason = format!("{}, run with `--force-unsafe` to allow it", reason);
+            warn!(msg, reason);
+        }
+
+        if resolution == Resolution::Skip
+            || refused.is_some()
+            || apply(backend, &source, &target, resolution).is_err()
+        {
+            decisions.insert(target, Decision::skip("skipped on request"));
+        } else if resolution == Resolution::Overwrite {
+            decisions.insert(target, Decision::Replace);
+        }
+    }
+
+    decisions
+}
+
+fn apply<B: LinkBackend>(
+    backend: &B,
+    source: &str,
+    target: &str,
+    resolution: Resolution,
+) -> Result<(), ()> {
+    let res = match resolution {
+        Resolution::Overwrite | Resolution::Skip => Ok(()),
+        Resolution::Backup => {
+            let backup = free_path(backend, &format!("{}.bak", target));
+            backend.rename(target, &backup).inspect(|_| {
+                let msg = format!("Backed up {} to {}", target, backup);
+                info!(msg);
+            })
+        }
+        Resolution::Adopt => adopt(backend, source, target),
+    };
+
+    res.map_err(|e| {
+        let msg = format!("Failed to resolve conflict of target: {}", target);
+        let raw_err = format!("{:?}", e);
+        warn!(msg, raw_err);
+    })
+}
+
+/// Move `target` in place of `source`
+///
+/// The source is stashed until the target is in place, and put back if moving the target fails.
+fn adopt<B: LinkBackend>(backend: &B, source: &str, target: &str) -> Result<(), IoError> {
+    let stash = free_path(backend, &format!("{}.ezcfg-stash", source));
+    backend.rename(source, &stash)?;
+
+    if let Err(e) = backend.rename(target, source) {
+        if let Err(restore_err) = backend.rename(&stash, source) {
+            let msg = format!("Failed to restore source: {} from {}", source, stash);
+            let raw_err = format!("{:?}", restore_err);
+            warn!(msg, raw_err);
+        }
+        return Err(e);
+    }
+
+    if let Err(e) = backend.remove(&stash) {
+        let msg = format!("Failed to remove the previous source: {}", stash);
+        let raw_err = format!("{:?}", e);
+        warn!(msg, raw_err);
+    }
+    Ok(())
+}
+
+/// `path`, or `<path>.<n>` with the first free `n`
+fn free_path<B: LinkBackend>(backend: &B, path: &str) -> String {
+    let is_free = |path: &str| matches!(backend.inspect(path), Ok(Entry::Missing));
+
+    if is_free(path) {
+        return path.to_string();
+    }
+    (1..)
+        .map(|n| format!("{}.{}", path, n))
+        .find(|path| is_free(path))
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+
+    use ezcfg_linker::MemoryBackend;
+
+    use super::*;
+
+    fn config() -> Config {
+        Config::new(
+            vec![
+                ("/dotfiles/a".to_string(), "/home/a".to_string()),
+                ("/dotfiles/b".to_string(), "/home/b".to_string()),
+                ("/dotfiles/c".to_string(), "/home/c".to_string()),
+                ("/dotfiles/d".to_string(), "/home/d".to_string()),
+            ],
+            false,
+        )
+    }
+
+    fn backend() -> MemoryBackend {
+        MemoryBackend::new()
+            .with_file("/dotfiles/a")
+            .with_file("/dotfiles/b")
+            .with_dir("/dotfiles/c")
+            .with_file("/dotfiles/d")
+            .with_file("/home/a")
+            .with_file("/home/b")
+            .with_file("/home/b.bak")
+            .with_file("/home/c")
+            .with_symlink("/home/d", "/dotfiles/d")
+    }
+
+    fn answers(answers: &[&str]) -> impl FnMut(&str) -> String {
+        let mut answers = answers
+            .iter()
+            .map(|answer| answer.to_string())
+            .collect::<VecDeque<_>>();
+        move |_| answers.pop_front().unwrap_or_default()
+    }
+
+    #[test]
+    fn it_should_apply_each_answer() {
+        let backend = backend();
+
+        let decisions =
+            resolve_conflicts(&config(), &backend, None, answers(&["o", "?", "b", "a"]));
+
+        assert_eq!(
+            decisions.into_iter().collect::<Vec<_>>(),
+            vec![("/home/a".to_string(), Decision::Replace)]
+        );
+        let entries = backend.entries();
+        assert_eq!(entries["/home/a"], Entry::File);
+        assert!(!entries.contains_key("/home/b"));
+        assert_eq!(entries["/home/b.bak.1"], Entry::File);
+        assert!(!entries.contains_key("/home/c"));
+        assert_eq!(entries["/dotfiles/c"], Entry::File);
+        assert_eq!(
+            entries["/home/d"],
+            Entry::Symlink("/dotfiles/d".to_string())
+        );
+    }
+
+    #[test]
+    fn it_should_apply_to_all() {
+        let backend = backend();
+
+        let decisions = resolve_conflicts(&config(), &backend, None, answers(&["d", "s!"]));
+
+        assert_eq!(
+            decisions.keys().collect::<Vec<_>>(),
+            vec!["/home/a", "/home/b", "/home/c"]
+        );
+        assert!(decisions
+            .values()
+            .all(|decision| *decision == Decision::skip("skipped on request")));
+        assert_eq!(backend.entries()["/home/a"], Entry::File);
+    }
+
+    /// A [`MemoryBackend`] which fails to move anything to `/dotfiles`
+    struct NoRenameIntoDotfiles(MemoryBackend);
+
+    impl LinkBackend for NoRenameIntoDotfiles {
+        fn create(&self, source: &str, target: &str) -> Result<(), IoError> {
+            self.0.create(source, target)
+        }
+
+        fn remove(&self, target: &str) -> Result<(), IoError> {
+            self.0.remove(target)
+        }
+
+        fn rename(&self, from: &str, to: &str) -> Result<(), IoError> {
+            match from.starts_with("/home") && to.starts_with("/dotfiles") {
+                true => Err(IoError::other("read-only dotfiles")),
+                false => self.0.rename(from, to),
+            }
+        }
+
+        fn write(&self, path: &str, contents: &[u8], mode: u32) -> Result<(), IoError> {
+            self.0.write(path, contents, mode)
+        }
+
+        fn mode(&self, path: &str) -> Result<u32, IoError> {
+            self.0.mode(path)
+        }
+
+        fn set_mode(&self, path: &str, mode: u32) -> Result<(), IoError> {
+            self.0.set_mode(path, mode)
+        }
+
+        fn inspect(&self, path: &str) -> Result<Entry, IoError> {
+            self.0.inspect(path)
+        }
+    }
+
+    #[test]
+    fn it_should_keep_the_source_when_adopting_fails() {
+        let backend = NoRenameIntoDotfiles(backend());
+
+        let decisions = resolve_conflicts(&config(), &backend, None, answers(&["a!"]));
+
+        assert_eq!(decisions["/home/c"], Decision::skip("skipped on request"));
+        let entries = backend.0.entries();
+        assert_eq!(entries["/dotfiles/c"], Entry::Dir);
+        assert_eq!(entries["/home/c"], Entry::File);
+        assert!(entries.keys().all(|path| !path.contains("stash")));
+    }
+
+    #[test]
+    fn it_should_not_overwrite_guarded_targets() {
+        let backend = backend().with_file("/dotfiles/e").with_file("/etc/e");
+        let mut config = config();
+        config
+            .links
+            .push(("/dotfiles/e".to_string(), "/etc/e".to_string()));
+        config.target_roots = vec!["/home".into()];
+        let guard = Guard::new(&config);
+
+        let decisions = resolve_conflicts(&config, &backend, Some(&guard), answers(&["o!"]));
+
+        assert_eq!(decisions["/etc/e"], Decision::skip("skipped on request"));
+        assert_eq!(backend.entries()["/etc/e"], Entry::File);
+        assert_eq!(decisions["/home/a"], Decision::Replace);
+    }
+}
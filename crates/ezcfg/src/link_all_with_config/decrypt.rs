@@ -0,0 +1,262 @@
+use std::{
+    fmt::Debug,
+    fs::{read, symlink_metadata},
+    io::ErrorKind as IoErrorKind,
+    path::Path,
+};
+
+use ezcfg_cli::{info, warn};
+use ezcfg_config::Config;
+use ezcfg_linker::{LinkBackend, LinkReport, SkipReason};
+
+use super::guard::Guard;
+use crate::{
+    interrupt::is_interrupted,
+    secret::{decrypt, Keyring},
+};
+
+enum Outcome {
+    Decrypted,
+    Skipped(SkipReason),
+    Failed,
+}
+
+/// Decrypt each of `secrets` to its target, readable by the owner only unless `mode` says otherwise
+///
+/// Existing targets are only replaced with `rewrite = true`, and when `guard` allows it.
+/// Targets are removed and written through `backend`, so that `--atomic` can roll them back.
+pub fn decrypt_all<B: LinkBackend, F: FnMut(&str) -> String>(
+    config: &Config,
+    backend: &B,
+    secrets: &[(String, String)],
+    guard: Option<&Guard>,
+    keyring: &mut Keyring<F>,
+) -> LinkReport {
+    let mut report = LinkReport::default();
+
+    for (source, target) in secrets {
+        if is_interrupted() {
+            report.cancelled += 1;
+            let msg = format!("Skipped {}: {}", target, SkipReason::Cancelled);
+            warn!(msg);
+            continue;
+        }
+
+        match decrypt_secret(
+            config,
+            backend,
+            Path::new(source),
+            Path::new(target),
+            guard,
+            keyring,
+        ) {
+            Outcome::Decrypted => {
+                report.linked += 1;
+                let msg = format!("Decrypted: {}", target);
+                info!(msg);
+            }
+            Outcome::Skipped(SkipReason::UpToDate) => report.up_to_date += 1,
+            Outcome::Skipped(reason) => {
+                report.skipped += 1;
+                let msg = format!("Skipped {}: {}", target, reason);
+                warn!(msg);
+            }
+            Outcome::Failed => report.failed += 1,
+        }
+    }
+
+    report
+}
+
+fn decrypt_secret<B: LinkBackend, F: FnMut(&str) -> String>(
+    config: &Config,
+    backend: &B,
+    source: &Path,
+    target: &Path,
+    guard: Option<&Guard>,
+    keyring: &mut Keyring<F>,
+) -> Outcome {
+    let ciphertext = match read(source) {
+        Ok(ciphertext) => ciphertext,
+        Err(e) if e.kind() == IoErrorKind::NotFound => {
+            return Outcome::Skipped(SkipReason::Rejected("source does not exist".to_string()))
+        }
+        Err(e) => return failed(format!("Failed to read source: {:?}", source), e),
+    };
+    let Ok(key) = keyring.key_for(&ciphertext) else {
+        return Outcome::Failed;
+    };
+    let plaintext = match decrypt(&ciphertext, key) {
+        Ok(plaintext) => plaintext,
+        Err(e) => return failed(format!("Failed to decrypt source: {:?}", source), e),
+    };
+
+    let mode = config
+        .link_options(target.to_str().unwrap())
+        .and_then(|options| options.mode)
+        .unwrap_or(0o600);
+
+    match symlink_metadata(target) {
+        Ok(metadata) if metadata.is_file() && read(target).is_ok_and(|c| c == plaintext) => {
+            let target_str = target.to_str().unwrap();
+            if !backend
+                .mode(target_str)
+                .is_ok_and(|current| current == mode)
+            {
+                if let Err(e) = backend.set_mode(target_str, mode) {
+                    return failed(format!("Failed to restrict target: {:?}", target), e);
+                }
+            }
+            return Outcome::Skipped(SkipReason::UpToDate);
+        }
+        Ok(_) if !config.rewrite => {
+            return Outcome::Skipped(SkipReason::Rejected(
+                "target already exists, set `rewrite = true` to replace it".to_string(),
+            ))
+        }
+        Ok(_) => {
+            if let Some(Err(reason)) = guard.map(|guard| guard.check_removal(source, target)) {
+                return Outcome::Skipped(SkipReason::Rejected(format!(
+                    "refused to replace target, {}, run with `--force-unsafe` to allow it",
+                    reason
+                )));
+            }
+            if let Err(e) = backend.remove(target.to_str().unwrap()) {
+                return failed(format!("Failed to remove target: {:?}", target), e);
+            }
+        }
+        Err(_) => {}
+    }
+
+    match backend.write(target.to_str().unwrap(), &plaintext, mode) {
+        Ok(_) => Outcome::Decrypted,
+        Err(e) => failed(format!("Failed to write target: {:?}", target), e),
+    }
+}
+
+fn failed<E: Debug>(msg: String, e: E) -> Outcome {
+    let raw_err = format!("{:?}", e);
+    warn!(msg, raw_err);
+    Outcome::Failed
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        fs::{metadata, read_to_string, write},
+        os::unix::fs::PermissionsExt,
+    };
+
+    use age::x25519::Identity;
+    use assert_fs::prelude::{FileWriteStr, PathChild};
+    use ezcfg_config::LinkOptions;
+    use ezcfg_linker::{FsBackend, JournalBackend};
+    use ezcfg_test::test_in_temp_dir;
+
+    use super::*;
+    use crate::secret::{encrypt, generate_identity, Key};
+
+    test_in_temp_dir!(it_should_decrypt_secrets_to_their_targets, |temp_dir| {
+        let identity_path = temp_dir.child("identity.txt");
+        let identity: Identity = generate_identity(identity_path.path()).unwrap();
+        let ciphertext = encrypt(b"machine host", &Key::Identity(Box::new(identity))).unwrap();
+
+        let source = temp_dir.child("netrc.age");
+        write(source.path(), ciphertext).unwrap();
+        let target = temp_dir.child("home/.netrc");
+        let conflict = temp_dir.child("conflict");
+        conflict.write_str("old").unwrap();
+
+        let path =
+            |child: &assert_fs::fixture::ChildPath| child.path().to_str().unwrap().to_string();
+        let secrets = vec![
+            (path(&source), path(&target)),
+            (path(&source), path(&conflict)),
+            (
+                path(&temp_dir.child("missing.age")),
+                path(&temp_dir.child("missing")),
+            ),
+        ];
+        let config = Config::new(secrets.clone(), false);
+        let mut keyring =
+            Keyring::new(identity_path.path().to_path_buf(), |_: &str| unreachable!());
+
+        let report = decrypt_all(&config, &FsBackend, &secrets, None, &mut keyring);
+        assert_eq!((report.linked, report.skipped, report.failed), (1, 2, 0));
+        assert_eq!(read_to_string(target.path()).unwrap(), "machine host");
+        assert_eq!(
+            metadata(target.path()).unwrap().permissions().mode() & 0o777,
+            0o600
+        );
+        assert_eq!(read_to_string(conflict.path()).unwrap(), "old");
+
+        let report = decrypt_all(&config, &FsBackend, &secrets[..1], None, &mut keyring);
+        assert_eq!(report.up_to_date, 1);
+
+        let mut config = Config::new(secrets.clone(), true);
+        config.link_options.insert(
+            path(&conflict),
+            LinkOptions {
+                mode: Some(0o640),
+                ..Default::default()
+            },
+        );
+        let guard = Guard::new(&config);
+        let report = decrypt_all(
+            &config,
+            &FsBackend,
+            &secrets[1..2],
+            Some(&guard),
+            &mut keyring,
+        );
+        assert_eq!(report.skipped, 1);
+
+        config.target_roots = vec![temp_dir.path().to_path_buf()];
+        let guard = Guard::new(&config);
+        let report = decrypt_all(
+            &config,
+            &FsBackend,
+            &secrets[1..2],
+            Some(&guard),
+            &mut keyring,
+        );
+        assert_eq!(report.linked, 1);
+        assert_eq!(read_to_string(conflict.path()).unwrap(), "machine host");
+        assert_eq!(
+            metadata(conflict.path()).unwrap().permissions().mode() & 0o777,
+            0o640
+        );
+    });
+
+    test_in_temp_dir!(it_should_roll_back_decrypted_secrets, |temp_dir| {
+        let identity_path = temp_dir.child("identity.txt");
+        let identity: Identity = generate_identity(identity_path.path()).unwrap();
+        let ciphertext = encrypt(b"machine host", &Key::Identity(Box::new(identity))).unwrap();
+
+        let source = temp_dir.child("netrc.age");
+        write(source.path(), ciphertext).unwrap();
+        let conflict = temp_dir.child("home/.netrc");
+        conflict.write_str("old").unwrap();
+        let new = temp_dir.child("home/new/.netrc");
+
+        let path =
+            |child: &assert_fs::fixture::ChildPath| child.path().to_str().unwrap().to_string();
+        let secrets = vec![
+            (path(&source), path(&conflict)),
+            (path(&source), path(&new)),
+        ];
+        let config = Config::new(secrets.clone(), true);
+        let mut keyring =
+            Keyring::new(identity_path.path().to_path_buf(), |_: &str| unreachable!());
+
+        let journal = JournalBackend::new();
+        let report = decrypt_all(&config, &journal, &secrets, None, &mut keyring);
+        assert_eq!(report.linked, 2);
+        assert_eq!(read_to_string(conflict.path()).unwrap(), "machine host");
+
+        journal.rollback().unwrap();
+        assert_eq!(read_to_string(conflict.path()).unwrap(), "old");
+        assert!(!temp_dir.child("home/new").exists());
+        assert_eq!(temp_dir.child("home").read_dir().unwrap().count(), 1);
+    });
+}
@@ -0,0 +1,207 @@
+use std::{
+    env::{self, current_dir},
+    path::{Component, Path, PathBuf},
+};
+
+use ezcfg_config::Config;
+
+/// The paths a run must never remove to make room for a link
+pub struct Guard {
+    home: Option<PathBuf>,
+    dotfiles: PathBuf,
+    roots: Vec<PathBuf>,
+}
+
+impl Guard {
+    /// Guard the home directory, the dotfiles in the current directory,
+    /// and everything outside the target roots of `config`
+    pub fn new(config: &Config) -> Self {
+        let home = env::var_os("HOME")
+            .filter(|home| !home.is_empty())
+            .map(|home| real_path(Path::new(&home)));
+        let roots = match config.target_roots.is_empty() {
+            true => home.iter().cloned().collect(),
+            false => config
+                .target_roots
+                .iter()
+                .map(|root| real_path(root))
+                .collect(),
+        };
+
+        Guard {
+            home,
+            dotfiles: real_path(&current_dir().unwrap()),
+            roots,
+        }
+    }
+
+    /// Why removing `target` to link `source` there would be unsafe, if it would
+    pub fn check_removal(&self, source: &Path, target: &Path) -> Result<(), &'static str> {
+        let target = real_path(target);
+
+        if target.parent().is_none() {
+            return Err("it is the root directory");
+        }
+        if self.home.as_ref() == Some(&target) {
+            return Err("it is the home directory");
+        }
+        if self.dotfiles.starts_with(&target) {
+            return Err("it contains the dotfiles");
+        }
+        if target.starts_with(&self.dotfiles) {
+            return Err("it is inside the dotfiles");
+        }
+        if real_path(source).starts_with(&target) {
+            return Err("it contains the source");
+        }
+        if !self
+            .roots
+            .iter()
+            .any(|root| target != *root && target.starts_with(root))
+        {
+            return Err("it is outside the target roots");
+        }
+
+        Ok(())
+    }
+}
+
+/// `path` made absolute, without `.` and `..`, and with the soft links of its parent resolved
+///
+/// The last component is kept as is, since removing a soft link does not touch what it points to.
+fn real_path(path: &Path) -> PathBuf {
+    let mut normal = PathBuf::new();
+    for component in current_dir().unwrap().join(path).components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normal.pop();
+            }
+            component => normal.push(component),
+        }
+    }
+
+    match (normal.parent(), normal.file_name()) {
+        (Some(parent), Some(name)) => parent
+            .canonicalize()
+            .map(|parent| parent.join(name))
+            .unwrap_or(normal),
+        _ => normal,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::os::unix::fs::symlink;
+
+    use assert_fs::prelude::{PathChild, PathCreateDir};
+    use ezcfg_test::test_in_temp_dir;
+
+    use super::*;
+
+    fn guard(temp_dir: &Path) -> Guard {
+        Guard {
+            home: Some(real_path(&temp_dir.join("home"))),
+            dotfiles: real_path(&temp_dir.join("home/.dotfiles")),
+            roots: vec![real_path(&temp_dir.join("home"))],
+        }
+    }
+
+    test_in_temp_dir!(it_should_allow_targets_inside_the_roots, |temp_dir| {
+        let guard = guard(temp_dir.path());
+        let source = temp_dir.child("home/.dotfiles/zsh/.zshrc");
+
+        assert_eq!(
+            guard.check_removal(source.path(), temp_dir.child("home/.zshrc").path()),
+            Ok(())
+        );
+        assert_eq!(
+            guard.check_removal(source.path(), temp_dir.child("home/.config/nvim").path()),
+            Ok(())
+        );
+    });
+
+    test_in_temp_dir!(it_should_refuse_the_root_directory, |temp_dir| {
+        let guard = guard(temp_dir.path());
+        let source = temp_dir.child("home/.dotfiles/x");
+
+        assert_eq!(
+            guard.check_removal(source.path(), Path::new("/")),
+            Err("it is the root directory")
+        );
+        assert_eq!(
+            guard.check_removal(source.path(), Path::new("/tmp/../..")),
+            Err("it is the root directory")
+        );
+    });
+
+    test_in_temp_dir!(it_should_refuse_the_home_directory, |temp_dir| {
+        let guard = guard(temp_dir.path());
+        let source = temp_dir.child("home/.dotfiles/x");
+
+        assert_eq!(
+            guard.check_removal(source.path(), temp_dir.child("home").path()),
+            Err("it is the home directory")
+        );
+        assert_eq!(
+            guard.check_removal(source.path(), temp_dir.child("home/.config/..").path()),
+            Err("it is the home directory")
+        );
+    });
+
+    test_in_temp_dir!(it_should_refuse_the_dotfiles, |temp_dir| {
+        let guard = guard(temp_dir.path());
+        let source = temp_dir.child("home/.dotfiles/x");
+
+        assert_eq!(
+            guard.check_removal(source.path(), temp_dir.child("home/.dotfiles").path()),
+            Err("it contains the dotfiles")
+        );
+        assert_eq!(
+            guard.check_removal(source.path(), temp_dir.child("home/.dotfiles/zsh").path()),
+            Err("it is inside the dotfiles")
+        );
+    });
+
+    test_in_temp_dir!(it_should_refuse_ancestors_of_the_source, |temp_dir| {
+        let mut guard = guard(temp_dir.path());
+        guard.roots.push(real_path(temp_dir.path()));
+        let source = temp_dir.child("elsewhere/nvim/init.lua");
+
+        assert_eq!(
+            guard.check_removal(source.path(), temp_dir.child("elsewhere").path()),
+            Err("it contains the source")
+        );
+        assert_eq!(
+            guard.check_removal(source.path(), source.path()),
+            Err("it contains the source")
+        );
+    });
+
+    test_in_temp_dir!(it_should_refuse_targets_outside_the_roots, |temp_dir| {
+        let guard = guard(temp_dir.path());
+        let source = temp_dir.child("home/.dotfiles/x");
+
+        assert_eq!(
+            guard.check_removal(source.path(), Path::new("/etc/hosts")),
+            Err("it is outside the target roots")
+        );
+        assert_eq!(
+            guard.check_removal(source.path(), temp_dir.child("home/../x").path()),
+            Err("it is outside the target roots")
+        );
+    });
+
+    test_in_temp_dir!(it_should_resolve_soft_links_of_the_parent, |temp_dir| {
+        let guard = guard(temp_dir.path());
+        let source = temp_dir.child("home/.dotfiles/x");
+        temp_dir.child("home").create_dir_all().unwrap();
+        let shortcut = temp_dir.child("shortcut");
+        symlink(temp_dir.path(), shortcut.path()).unwrap();
+
+        assert_eq!(
+            guard.check_removal(source.path(), shortcut.child("home").path()),
+            Err("it is the home directory")
+        );
+    });
+}
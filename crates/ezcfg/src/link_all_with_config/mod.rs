@@ -1,24 +1,229 @@
 mod checker;
+mod conflict;
+mod decrypt;
+mod guard;
+mod permission;
 
-use checker::check_path;
+use std::{collections::BTreeMap, sync::Arc};
+
+use checker::{check_overwrite, check_path};
+use conflict::resolve_conflicts;
+use decrypt::decrypt_all;
+use ezcfg_cli::{
+    error, info,
+    prompt::{ask, ask_secret},
+    warn,
+};
 use ezcfg_config::Config;
-use ezcfg_linker::link_all_with_filter;
+use ezcfg_linker::{
+    Decision, JournalBackend, JournalEntry, LinkBackend, LinkEventSink, LinkReport, Linker,
+    PoolStats, SkipReason, SpinnerSink, TimingSink,
+};
+use guard::Guard;
+use permission::{apply_permissions, warn_open_permissions};
+
+use crate::{
+    interrupt::{catch_interrupts, interrupted},
+    secret::{identity_path, Keyring},
+};
+
+/// Switches of a run, given on the command line
+#[derive(Debug, Default, Clone, Copy)]
+pub struct LinkFlags {
+    /// Show how each conflicting target differs from its source
+    pub show_diff: bool,
+    /// Ask what to do with each conflicting target before linking
+    pub interactive: bool,
+    /// Allow replacing targets which the [`Guard`] protects
+    pub force_unsafe: bool,
+    /// How many links to create at the same time, overriding `jobs` of the config
+    pub jobs: Option<usize>,
+    /// Print the slowest links after the run
+    pub timings: bool,
+    /// Let Ctrl-C stop the run once the conflicts are resolved, see [`catch_interrupts`]
+    ///
+    /// It is left to quit the prompts of `interactive` until then.
+    pub catch_interrupts: bool,
+}
+
+/// How many links `--timings` prints
+const SLOWEST_COUNT: usize = 10;
+
+pub fn link_all_with_config<B: LinkBackend + Clone>(
+    config: &Config,
+    backend: B,
+    flags: LinkFlags,
+) -> LinkReport {
+    let guard = (!flags.force_unsafe).then(|| Guard::new(config));
+    let resolved = match flags.interactive {
+        true => resolve_conflicts(config, &backend, guard.as_ref(), ask),
+        false => BTreeMap::new(),
+    };
+    if flags.catch_interrupts {
+        catch_interrupts();
+    }
+
+    let (secrets, links): (Vec<_>, Vec<_>) = config
+        .links
+        .iter()
+        .cloned()
+        .partition(|(_, target)| config.is_encrypted(target));
+
+    let sink = TimingSink::new(SpinnerSink);
+    let mut linker = Linker::new()
+        .with_backend(backend.clone())
+        .with_cancel(interrupted());
+    if let Some(jobs) = flags.jobs.or(config.jobs) {
+        linker = linker.with_jobs(jobs);
+    }
+    // Modes are applied once the link is in place, so that they are only
+    // changed for the links which end up linked, and rolled back with them
+    let mut report = linker.link_all_with_hooks(
+        &links,
+        |source, target, _idx, _len| match resolved.get(target) {
+            Some(Decision::Replace) => check_overwrite(&backend, source),
+            Some(decision) => decision.clone(),
+            None => check_path(config, &backend, guard.as_ref(), source, target, flags),
+        },
+        |task| sink.on_waiting(task),
+        |task, handle| {
+            sink.on_linking(task, &handle);
+            handle
+        },
+        |task, handle| {
+            apply_permissions(config, &backend, &task.source, &task.target);
+            sink.on_success(task, handle)
+        },
+        |task, err, handle| sink.on_fail(task, handle, err),
+        |task, reason| {
+            if *reason == SkipReason::UpToDate {
+                apply_permissions(config, &backend, &task.source, &task.target);
+            }
+            sink.on_skipped(task, reason)
+        },
+        |task, handle| sink.on_cancelled(task, handle),
+    );
+
+    if flags.timings {
+        print_slowest(&sink);
+        print_pool_stats(linker.pool_stats());
+    }
+
+    if !secrets.is_empty() {
+        let mut keyring = Keyring::new(identity_path(), ask_secret);
+        report += decrypt_all(config, &backend, &secrets, guard.as_ref(), &mut keyring);
+    }
+
+    warn_open_permissions(&config.links);
+    report
+}
+
+fn print_slowest(sink: &TimingSink<SpinnerSink>) {
+    let slowest = sink.slowest(SLOWEST_COUNT);
+    if slowest.is_empty() {
+        return;
+    }
+
+    let msg = format!("The {} slowest link(s)", slowest.len());
+    let timings = slowest
+        .iter()
+        .map(|timing| {
+            format!(
+                "{:?} (waited {:?}) {}",
+                timing.run, timing.wait, timing.target
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n    ");
+    info!(msg, timings);
+}
+
+/// Nothing is printed when the links have been created on the calling thread
+fn print_pool_stats(stats: Option<PoolStats>) {
+    let Some(stats) = stats else {
+        return;
+    };
+
+    let msg = format!("The pool ran {} task(s)", stats.completed);
+    let times = [
+        format!(
+            "waited {:?} on average, {:?} at most",
+            stats.avg_wait, stats.max_wait
+        ),
+        format!(
+            "ran {:?} on average, {:?} at most",
+            stats.avg_run, stats.max_run
+        ),
+        format!("{} thread(s) respawned", stats.respawned),
+    ]
+    .join("\n    ");
+    info!(msg, times);
+}
+
+/// Link everything or nothing
+///
+/// Targets to rewrite are moved aside instead of being deleted,
+/// and every change is rolled back if any link fails or the run is interrupted.
+/// The report is returned either way, as an error when the changes have been rolled back.
+pub fn link_all_with_config_atomically(
+    config: &Config,
+    flags: LinkFlags,
+) -> Result<LinkReport, LinkReport> {
+    let journal = Arc::new(JournalBackend::new());
+    let report = link_all_with_config(config, journal.clone(), flags);
 
-pub fn link_all_with_config(config: &Config) {
-    link_all_with_filter(&config.links, |source, target, _idx, _len| {
-        check_path(config, source, target).is_ok()
-    });
+    if report.failed == 0 && report.cancelled == 0 {
+        if let Err(e) = journal.commit() {
+            let msg = "Failed to delete some of the rewritten targets";
+            let raw_err = format!("{:?}", e);
+            warn!(msg, raw_err);
+        }
+        return Ok(report);
+    }
+
+    let changes = journal.journal().len();
+    let cause = match report.cancelled {
+        0 => "Some links failed",
+        _ => "Interrupted",
+    };
+    match journal.rollback() {
+        Ok(_) => {
+            let msg = format!("{}, rolled back {} change(s)", cause, changes);
+            info!(msg);
+        }
+        Err(e) => {
+            let msg = format!("{} and the rollback is incomplete", cause);
+            let raw_err = format!("{:?}", e);
+            let leftover = journal
+                .journal()
+                .iter()
+                .filter_map(|entry| match entry {
+                    JournalEntry::MovedAside { target, stash } => {
+                        Some(format!("{} was moved to {}", target, stash))
+                    }
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+                .join("\n    ");
+            error!(msg, raw_err, leftover);
+        }
+    }
+
+    Err(report)
 }
 
 #[cfg(test)]
 mod tests {
+    use std::fs::{read_to_string, symlink_metadata, write};
+
     use super::*;
+    use ezcfg_linker::FsBackend;
     use ezcfg_test::*;
 
     fn test_in_temp_dir_with_config(config: Config) {
         run_in_temp_dir_with_config(config, |links| {
             let config = Config::new(links.clone(), false);
-            link_all_with_config(&config)
+            link_all_with_config(&config, FsBackend, LinkFlags::default());
         })
     }
 
@@ -38,6 +243,55 @@ mod tests {
                 ("anywhere/f.txt".to_string(), "j.txt".to_string()),
             ],
             rewrite: false,
+            ..Default::default()
         })
     }
+
+    #[test]
+    fn it_should_roll_back_rewritten_targets_when_a_link_fails() {
+        run_in_temp_dir_with_config(
+            Config::new(
+                vec![
+                    ("a.txt".to_string(), "a.txt".to_string()),
+                    ("b.txt".to_string(), "missing/b.txt".to_string()),
+                ],
+                true,
+            ),
+            |links| {
+                let existing = &links[0].1;
+                write(existing, "existing").unwrap();
+
+                let mut config = Config::new(links.clone(), true);
+                let parent = std::path::Path::new(existing).parent().unwrap();
+                config.target_roots = vec![parent.to_path_buf()];
+                assert!(link_all_with_config_atomically(&config, LinkFlags::default()).is_err());
+
+                assert!(!symlink_metadata(existing).unwrap().is_symlink());
+                assert_eq!(read_to_string(existing).unwrap(), "existing");
+            },
+        )
+    }
+
+    #[test]
+    fn it_should_commit_when_every_link_succeeds() {
+        run_in_temp_dir_with_config(
+            Config::new(vec![("a.txt".to_string(), "a.txt".to_string())], true),
+            |links| {
+                let (source, target) = &links[0];
+                write(target, "existing").unwrap();
+
+                let parent = std::path::Path::new(target).parent().unwrap();
+                let mut config = Config::new(links.clone(), true);
+                config.target_roots = vec![parent.to_path_buf()];
+                assert!(link_all_with_config_atomically(&config, LinkFlags::default()).is_ok());
+
+                assert!(symlink_metadata(target).unwrap().is_symlink());
+                assert_eq!(
+                    read_to_string(target).unwrap(),
+                    read_to_string(source).unwrap()
+                );
+                assert_eq!(parent.read_dir().unwrap().count(), 1);
+            },
+        )
+    }
 }
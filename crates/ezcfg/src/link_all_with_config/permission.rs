@@ -0,0 +1,229 @@
+use std::{
+    fs::metadata,
+    os::unix::fs::PermissionsExt,
+    path::{Path, PathBuf},
+};
+
+use ezcfg_cli::warn;
+use ezcfg_config::Config;
+use ezcfg_linker::LinkBackend;
+use walkdir::WalkDir;
+
+/// Targets which tools refuse to use, or should not be used, when others can access them
+static SENSITIVE: [&str; 8] = [
+    ".ssh",
+    ".gnupg",
+    ".netrc",
+    ".pgpass",
+    ".aws/credentials",
+    ".docker/config.json",
+    ".kube/config",
+    ".git-credentials",
+];
+
+/// Apply the `mode` and `dir_mode` of the link from `source` to `target` to its source,
+/// through `backend`
+///
+/// Directories are walked, `mode` goes to the files and `dir_mode` to the directories.
+/// Entries which already have the right permissions are left untouched.
+pub fn apply_permissions<B: LinkBackend>(config: &Config, backend: &B, source: &str, target: &str) {
+    let Some(options) = config.link_options(target) else {
+        return;
+    };
+    if options.mode.is_none() && options.dir_mode.is_none() {
+        return;
+    }
+
+    for entry in WalkDir::new(source)
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| !entry.path_is_symlink())
+    {
+        let mode = match entry.file_type().is_dir() {
+            true => options.dir_mode,
+            false => options.mode,
+        };
+        let Some(mode) = mode else {
+            continue;
+        };
+        let path = entry.path().to_str().unwrap();
+        if backend.mode(path).is_ok_and(|current| current == mode) {
+            continue;
+        }
+        if let Err(e) = backend.set_mode(path, mode) {
+            let msg = format!("Failed to set permissions of: {:?}", entry.path());
+            let raw_err = format!("{:?}", e);
+            warn!(msg, raw_err);
+        }
+    }
+}
+
+/// Warn about the sensitive targets of `links` which the group or others can access
+pub fn warn_open_permissions(links: &[(String, String)]) {
+    for (_, target) in links.iter().filter(|(_, target)| is_sensitive(target)) {
+        let open = too_open(Path::new(target));
+        if open.is_empty() {
+            continue;
+        }
+
+        let msg = format!(
+            "Permissions of {} are too open, set `mode` and `dir_mode` of the link",
+            target
+        );
+        let list = open
+            .iter()
+            .map(|(path, mode)| format!("{:o} {}", mode, path.display()))
+            .collect::<Vec<_>>()
+            .join("\n    ");
+        warn!(msg, list);
+    }
+}
+
+fn is_sensitive(target: &str) -> bool {
+    let target = Path::new(target);
+    SENSITIVE.iter().any(|sensitive| {
+        target.ends_with(sensitive)
+            || target
+                .ancestors()
+                .skip(1)
+                .any(|ancestor| ancestor.ends_with(sensitive))
+    })
+}
+
+/// The entries at or below `path` which are too open, with their mode
+///
+/// Nothing may be writable by the group or others, and private files may not be
+/// readable by them either. Soft links are followed, so a link reports the permissions
+/// of its source.
+fn too_open(path: &Path) -> Vec<(PathBuf, u32)> {
+    WalkDir::new(path)
+        .follow_links(true)
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let metadata = metadata(entry.path()).ok()?;
+            let mode = metadata.permissions().mode() & 0o7777;
+            let public = metadata.is_dir() || is_public(entry.path());
+            let forbidden = match public {
+                true => 0o022,
+                false => 0o077,
+            };
+            (mode & forbidden != 0).then(|| (entry.path().to_path_buf(), mode))
+        })
+        .collect()
+}
+
+fn is_public(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "pub")
+        || path.file_name().is_some_and(|name| name == "known_hosts")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        fs::{set_permissions, Permissions},
+        os::unix::fs::symlink,
+    };
+
+    use assert_fs::prelude::{FileWriteStr, PathChild, PathCreateDir};
+    use ezcfg_config::LinkOptions;
+    use ezcfg_linker::{FsBackend, JournalBackend};
+    use ezcfg_test::test_in_temp_dir;
+
+    use super::*;
+
+    fn mode_of(path: &Path) -> u32 {
+        metadata(path).unwrap().permissions().mode() & 0o7777
+    }
+
+    test_in_temp_dir!(it_should_apply_modes_to_the_source, |temp_dir| {
+        let ssh = temp_dir.child("ssh");
+        ssh.child("config").write_str("Host *").unwrap();
+        ssh.child("keys/id_ed25519").write_str("key").unwrap();
+        let gitconfig = temp_dir.child("gitconfig");
+        gitconfig.write_str("").unwrap();
+        set_permissions(gitconfig.path(), Permissions::from_mode(0o644)).unwrap();
+
+        let path =
+            |child: &assert_fs::fixture::ChildPath| child.path().to_str().unwrap().to_string();
+        let mut config = Config::new(
+            vec![
+                (path(&ssh), "/home/.ssh".to_string()),
+                (path(&gitconfig), "/home/.gitconfig".to_string()),
+            ],
+            false,
+        );
+        config.link_options.insert(
+            "/home/.ssh".to_string(),
+            LinkOptions {
+                mode: Some(0o600),
+                dir_mode: Some(0o700),
+                ..Default::default()
+            },
+        );
+
+        for (source, target) in config.links.iter() {
+            apply_permissions(&config, &FsBackend, source, target);
+        }
+
+        assert_eq!(mode_of(ssh.path()), 0o700);
+        assert_eq!(mode_of(ssh.child("keys").path()), 0o700);
+        assert_eq!(mode_of(ssh.child("config").path()), 0o600);
+        assert_eq!(mode_of(ssh.child("keys/id_ed25519").path()), 0o600);
+        assert_eq!(mode_of(gitconfig.path()), 0o644);
+    });
+
+    test_in_temp_dir!(it_should_roll_back_modes, |temp_dir| {
+        let source = temp_dir.child("netrc");
+        source.write_str("machine host").unwrap();
+        set_permissions(source.path(), Permissions::from_mode(0o644)).unwrap();
+
+        let source_path = source.path().to_str().unwrap().to_string();
+        let mut config = Config::new(
+            vec![(source_path.clone(), "/home/.netrc".to_string())],
+            false,
+        );
+        config.link_options.insert(
+            "/home/.netrc".to_string(),
+            LinkOptions {
+                mode: Some(0o600),
+                ..Default::default()
+            },
+        );
+
+        let journal = JournalBackend::new();
+        apply_permissions(&config, &journal, &source_path, "/home/.netrc");
+        assert_eq!(mode_of(source.path()), 0o600);
+
+        journal.rollback().unwrap();
+        assert_eq!(mode_of(source.path()), 0o644);
+    });
+
+    test_in_temp_dir!(it_should_find_open_sensitive_targets, |temp_dir| {
+        let source = temp_dir.child("dotfiles/ssh_config");
+        source.write_str("Host *").unwrap();
+        set_permissions(source.path(), Permissions::from_mode(0o664)).unwrap();
+        temp_dir.child(".ssh").create_dir_all().unwrap();
+        let target = temp_dir.child(".ssh/config");
+        symlink(source.path(), target.path()).unwrap();
+
+        assert!(is_sensitive(target.path().to_str().unwrap()));
+        assert!(!is_sensitive("/home/.zshrc"));
+        assert_eq!(
+            too_open(target.path()),
+            vec![(target.path().to_path_buf(), 0o664)]
+        );
+
+        set_permissions(source.path(), Permissions::from_mode(0o600)).unwrap();
+        assert!(too_open(target.path()).is_empty());
+
+        let public_key = temp_dir.child(".ssh/id_ed25519.pub");
+        public_key.write_str("ssh-ed25519").unwrap();
+        set_permissions(public_key.path(), Permissions::from_mode(0o644)).unwrap();
+        set_permissions(temp_dir.child(".ssh").path(), Permissions::from_mode(0o755)).unwrap();
+        assert!(too_open(temp_dir.child(".ssh").path()).is_empty());
+
+        set_permissions(public_key.path(), Permissions::from_mode(0o664)).unwrap();
+        assert_eq!(too_open(temp_dir.child(".ssh").path()).len(), 1);
+    });
+}
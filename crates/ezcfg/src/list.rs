@@ -0,0 +1,136 @@
+use clap::ArgMatches;
+use ezcfg_config::Config;
+use serde_json::json;
+
+use crate::read_config::read_config;
+
+pub fn list(matches: &ArgMatches) -> Result<(), ()> {
+    let config = read_config().ok_or(())?;
+    let format = matches.get_one::<String>("format").unwrap();
+
+    let output = match format.as_str() {
+        "json" => format_json(&config),
+        "plain" => format_plain(&config),
+        _ => format_table(&config),
+    };
+    if !output.is_empty() {
+        println!("{}", output);
+    }
+
+    Ok(())
+}
+
+/// One `section source target` line per link, separated by tabs
+fn format_plain(config: &Config) -> String {
+    config
+        .links
+        .iter()
+        .map(|(source, target)| format!("{}\t{}\t{}", config.section, source, target))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn format_table(config: &Config) -> String {
+    let header = ("SECTION", "SOURCE", "TARGET");
+    let section = config.section.to_string();
+
+    let section_width = header.0.len().max(section.len());
+    let source_width = config
+        .links
+        .iter()
+        .map(|(source, _)| source.chars().count())
+        .fold(header.1.len(), usize::max);
+
+    let row = |section: &str, source: &str, target: &str| {
+        format!(
+            "{:section_width$}  {:source_width$}  {}",
+            section, source, target
+        )
+    };
+
+    let mut lines = vec![row(header.0, header.1, header.2)];
+    lines.extend(
+        config
+            .links
+            .iter()
+            .map(|(source, target)| row(&section, source, target)),
+    );
+    lines
+        .iter()
+        .map(|line| line.trim_end())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn format_json(config: &Config) -> String {
+    let links = config
+        .links
+        .iter()
+        .map(|(source, target)| {
+            json!({
+                "section": config.section.to_string(),
+                "source": source,
+                "target": target,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    serde_json::to_string_pretty(&links).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use ezcfg_config::Section;
+
+    use super::*;
+
+    fn config() -> Config {
+        Config {
+            links: vec![
+                (
+                    "/dotfiles/zsh/.zshrc".to_string(),
+                    "/home/.zshrc".to_string(),
+                ),
+                (
+                    "/dotfiles/nvim".to_string(),
+                    "/home/.config/nvim".to_string(),
+                ),
+            ],
+            section: Section::Linux,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn it_should_format_table() {
+        assert_eq!(
+            format_table(&config()),
+            "SECTION  SOURCE                TARGET\n\
+             linux    /dotfiles/zsh/.zshrc  /home/.zshrc\n\
+             linux    /dotfiles/nvim        /home/.config/nvim"
+        );
+    }
+
+    #[test]
+    fn it_should_format_plain() {
+        assert_eq!(
+            format_plain(&config()),
+            "linux\t/dotfiles/zsh/.zshrc\t/home/.zshrc\n\
+             linux\t/dotfiles/nvim\t/home/.config/nvim"
+        );
+        assert_eq!(format_plain(&Config::default()), "");
+    }
+
+    #[test]
+    fn it_should_format_json() {
+        let links: serde_json::Value = serde_json::from_str(&format_json(&config())).unwrap();
+
+        assert_eq!(
+            links,
+            json!([
+                { "section": "linux", "source": "/dotfiles/zsh/.zshrc", "target": "/home/.zshrc" },
+                { "section": "linux", "source": "/dotfiles/nvim", "target": "/home/.config/nvim" },
+            ])
+        );
+    }
+}
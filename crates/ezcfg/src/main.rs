@@ -1,13 +1,99 @@
+mod add;
+mod adopt;
+mod diff;
+mod encrypt;
+mod hooks;
+mod init;
+mod interrupt;
 mod link_all_with_config;
+mod list;
+mod prune;
 mod read_config;
+mod remove;
+mod secret;
+mod validate_config;
+mod watch;
+mod write_state;
 
-use ezcfg_cli::command::{cli, version};
-use link_all_with_config::link_all_with_config;
+use std::process::exit;
+
+use add::add;
+use adopt::adopt;
+use diff::diff;
+use encrypt::encrypt;
+use ezcfg_cli::{
+    command::{cli, version},
+    info, warn,
+};
+use ezcfg_config::{Config, FailurePolicy};
+use ezcfg_linker::FsBackend;
+use hooks::{run_post_link_hooks, run_pre_link_hooks};
+use init::init;
+use link_all_with_config::{link_all_with_config, link_all_with_config_atomically, LinkFlags};
+use list::list;
+use prune::prune;
 use read_config::read_config;
+use remove::remove;
+use validate_config::validate_config;
+use watch::watch;
+use write_state::write_state;
 
-fn link_all() {
+fn link_all(atomic: bool, flags: LinkFlags) {
     if let Some(config) = read_config() {
-        link_all_with_config(&config);
+        let Some(config) = validate_config(config) else {
+            exit(1);
+        };
+
+        if let Err(code) = link_with_hooks(&config, atomic, flags) {
+            exit(code);
+        }
+    }
+}
+
+/// Run the `pre_link` hooks, link a validated `config` and record it, then run the `post_link` hooks
+///
+/// The status to exit with is returned if a hook or a link failed, or the run was interrupted.
+fn link_with_hooks(config: &Config, atomic: bool, flags: LinkFlags) -> Result<(), i32> {
+    let pre_link = run_pre_link_hooks(config);
+    if pre_link.is_err() && config.hooks.on_failure == FailurePolicy::Abort {
+        return Err(1);
+    }
+
+    let res = match atomic {
+        true => link_all_with_config_atomically(config, flags),
+        false => Ok(link_all_with_config(config, FsBackend, flags)),
+    };
+    let (Ok(report) | Err(report)) = res;
+    let msg = format!("{}", report);
+    info!(msg);
+    let code = match report.cancelled {
+        0 => 1,
+        _ => 130,
+    };
+    if res.is_err() {
+        return Err(code);
+    }
+    write_state(config);
+    if report.cancelled > 0 {
+        warn!(
+            "Interrupted, the links skipped as cancelled have not been processed",
+            "Run ezcfg again to link them"
+        );
+        return Err(code);
+    }
+
+    if report.failed > 0 && config.hooks.on_failure == FailurePolicy::Abort {
+        warn!(
+            "Some links failed, the post_link hooks have not been run",
+            "Set `on_failure = \"continue\"` to run them anyway"
+        );
+        return Err(1);
+    }
+
+    let post_link = run_post_link_hooks(config);
+    match pre_link.is_err() || post_link.is_err() || report.failed > 0 {
+        true => Err(1),
+        false => Ok(()),
     }
 }
 
@@ -16,7 +102,33 @@ fn main() {
 
     if matches.get_flag("version") {
         version(option_env!("CARGO_PKG_VERSION").unwrap_or("N/A"));
-    } else {
-        link_all();
+        return;
+    }
+
+    let res = match matches.subcommand() {
+        Some(("add", sub_matches)) => add(sub_matches),
+        Some(("adopt", sub_matches)) => adopt(sub_matches),
+        Some(("diff", sub_matches)) => diff(sub_matches),
+        Some(("encrypt", sub_matches)) => encrypt(sub_matches),
+        Some(("init", sub_matches)) => init(sub_matches),
+        Some(("list", sub_matches)) => list(sub_matches),
+        Some(("prune", sub_matches)) => prune(sub_matches),
+        Some(("remove", sub_matches)) => remove(sub_matches),
+        Some(("watch", sub_matches)) => watch(sub_matches),
+        _ => {
+            let flags = LinkFlags {
+                show_diff: matches.get_flag("diff"),
+                interactive: matches.get_flag("interactive"),
+                force_unsafe: matches.get_flag("force-unsafe"),
+                jobs: matches.get_one::<u64>("jobs").map(|jobs| *jobs as usize),
+                timings: matches.get_flag("timings"),
+                catch_interrupts: true,
+            };
+            link_all(matches.get_flag("atomic"), flags);
+            Ok(())
+        }
+    };
+    if res.is_err() {
+        exit(1);
     }
 }
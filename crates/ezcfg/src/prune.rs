@@ -0,0 +1,285 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    env::{self, current_dir},
+    fs::read_link,
+    path::{Component, Path, PathBuf},
+};
+
+use clap::ArgMatches;
+use ezcfg_cli::{info, prompt::confirm, warn};
+use ezcfg_config::Config;
+use ezcfg_linker::{FsBackend, LinkBackend};
+use ezcfg_state::State;
+use walkdir::WalkDir;
+
+use crate::read_config::read_config;
+
+/// A soft link into the dotfiles which is not declared in the config
+#[derive(Debug, PartialEq, Eq)]
+pub struct Orphan {
+    pub target: PathBuf,
+    pub dest: PathBuf,
+}
+
+pub fn prune(matches: &ArgMatches) -> Result<(), ()> {
+    let config = read_config().ok_or(())?;
+    let dotfiles = current_dir().unwrap();
+    let roots = matches
+        .get_many::<String>("root")
+        .map(|roots| roots.map(PathBuf::from).collect())
+        .unwrap_or_else(default_roots);
+    let depth = *matches.get_one::<usize>("depth").unwrap();
+
+    let state_path = State::default_path();
+    let mut state = State::load(&state_path).unwrap_or_else(|e| {
+        let msg = format!("Failed to read state file: {:?}", state_path);
+        let raw_err = format!("{:?}", e);
+        warn!(msg, raw_err);
+        State::default()
+    });
+
+    let orphans = find_orphans(&config, &dotfiles, &roots, depth, &state);
+    if orphans.is_empty() {
+        info!("No orphaned link found");
+        return Ok(());
+    }
+
+    let msg = format!("Found {} orphaned link(s)", orphans.len());
+    let list = orphans
+        .iter()
+        .map(|orphan| format!("{} -> {}", orphan.target.display(), orphan.dest.display()))
+        .collect::<Vec<_>>()
+        .join("\n    ");
+    info!(msg, list);
+
+    if !matches.get_flag("yes") && !confirm("Remove them?") {
+        return Ok(());
+    }
+
+    let mut res = Ok(());
+    for orphan in orphans.iter() {
+        let target = orphan.target.to_str().unwrap();
+        match FsBackend.remove(target) {
+            Ok(_) => {
+                state.forget(target);
+            }
+            Err(e) => {
+                let msg = format!("Failed to remove link: {}", target);
+                let raw_err = format!("{:?}", e);
+                warn!(msg, raw_err);
+                res = Err(());
+            }
+        }
+    }
+
+    if let Err(e) = state.save(&state_path) {
+        let msg = format!("Failed to write state file: {:?}", state_path);
+        let raw_err = format!("{:?}", e);
+        warn!(msg, raw_err);
+    }
+
+    res
+}
+
+/// `$HOME` and `$XDG_CONFIG_HOME`, which defaults to `$HOME/.config`
+fn default_roots() -> Vec<PathBuf> {
+    let home = PathBuf::from(env::var("HOME").unwrap_or_default());
+    let config_home = match env::var("XDG_CONFIG_HOME") {
+        Ok(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => home.join(".config"),
+    };
+
+    vec![home, config_home]
+}
+
+/// Find the soft links pointing into `dotfiles` whose path is not a declared target
+///
+/// Both the links found under `roots` and the links recorded in `state` are considered,
+/// nothing inside `dotfiles` itself is scanned.
+pub fn find_orphans(
+    config: &Config,
+    dotfiles: &Path,
+    roots: &[PathBuf],
+    depth: usize,
+    state: &State,
+) -> Vec<Orphan> {
+    let declared = config
+        .links
+        .iter()
+        .map(|(_, target)| PathBuf::from(target))
+        .collect::<BTreeSet<_>>();
+
+    let mut orphans = BTreeMap::new();
+
+    roots
+        .iter()
+        .filter(|root| root.is_dir())
+        .flat_map(|root| {
+            WalkDir::new(root)
+                .max_depth(depth)
+                .follow_links(false)
+                .into_iter()
+                .filter_entry(|entry| !entry.path().starts_with(dotfiles))
+                .filter_map(|entry| entry.ok())
+                .filter(|entry| entry.path_is_symlink())
+        })
+        .for_each(|entry| {
+            let target = entry.path().to_path_buf();
+            if let Some(dest) = resolve_link(&target) {
+                orphans.insert(target, dest);
+            }
+        });
+
+    let declared_targets = config
+        .links
+        .iter()
+        .map(|(_, target)| target)
+        .collect::<Vec<_>>();
+    state
+        .orphans(&config.path, &declared_targets)
+        .into_iter()
+        .filter(|record| FsBackend.is_linked(&record.source, &record.target))
+        .for_each(|record| {
+            orphans.insert(PathBuf::from(&record.target), PathBuf::from(&record.source));
+        });
+
+    orphans
+        .into_iter()
+        .filter(|(target, dest)| dest.starts_with(dotfiles) && !declared.contains(target))
+        .map(|(target, dest)| Orphan { target, dest })
+        .collect()
+}
+
+/// Where `link` points to, relative destinations joined onto its parent, without `.` and `..`
+fn resolve_link(link: &Path) -> Option<PathBuf> {
+    let dest = read_link(link).ok()?;
+    let dest = match dest.is_absolute() {
+        true => dest,
+        false => link.parent()?.join(dest),
+    };
+
+    let mut normal = PathBuf::new();
+    for component in dest.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normal.pop();
+            }
+            component => normal.push(component),
+        }
+    }
+    Some(normal)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::os::unix::fs::symlink;
+
+    use assert_fs::prelude::{FileTouch, PathChild, PathCreateDir};
+    use ezcfg_state::{LinkMode, LinkRecord};
+    use ezcfg_test::test_in_temp_dir;
+
+    use super::*;
+
+    test_in_temp_dir!(it_should_find_undeclared_links_into_dotfiles, |temp_dir| {
+        let dotfiles = temp_dir.child("dotfiles");
+        dotfiles.child("kept").touch().unwrap();
+        dotfiles.child("dropped").touch().unwrap();
+        dotfiles.child("nested").create_dir_all().unwrap();
+        symlink(
+            dotfiles.child("kept").path(),
+            dotfiles.child("nested/inner").path(),
+        )
+        .unwrap();
+
+        let home = temp_dir.child("home");
+        home.child(".config/deep/deeper").create_dir_all().unwrap();
+        let elsewhere = temp_dir.child("elsewhere");
+        elsewhere.touch().unwrap();
+
+        let kept = home.child(".kept");
+        let dropped = home.child(".config/dropped");
+        let too_deep = home.child(".config/deep/deeper/dropped");
+        let unrelated = home.child(".unrelated");
+        symlink(dotfiles.child("kept").path(), kept.path()).unwrap();
+        symlink(dotfiles.child("dropped").path(), dropped.path()).unwrap();
+        symlink(dotfiles.child("dropped").path(), too_deep.path()).unwrap();
+        symlink(elsewhere.path(), unrelated.path()).unwrap();
+
+        let config = Config::new(
+            vec![(
+                dotfiles.child("kept").path().to_str().unwrap().to_string(),
+                kept.path().to_str().unwrap().to_string(),
+            )],
+            false,
+        );
+
+        let orphans = find_orphans(
+            &config,
+            dotfiles.path(),
+            &[home.path().to_path_buf(), dotfiles.path().to_path_buf()],
+            2,
+            &State::default(),
+        );
+
+        assert_eq!(
+            orphans,
+            vec![Orphan {
+                target: dropped.path().to_path_buf(),
+                dest: dotfiles.child("dropped").path().to_path_buf(),
+            }]
+        );
+    });
+
+    test_in_temp_dir!(it_should_resolve_relative_links, |temp_dir| {
+        let dotfiles = temp_dir.child("dotfiles");
+        dotfiles.child("dropped").touch().unwrap();
+        let home = temp_dir.child("home");
+        home.create_dir_all().unwrap();
+        let dropped = home.child(".dropped");
+        symlink("../dotfiles/./dropped", dropped.path()).unwrap();
+
+        let config = Config::new(vec![], false);
+        let orphans = find_orphans(
+            &config,
+            dotfiles.path(),
+            &[home.path().to_path_buf()],
+            1,
+            &State::default(),
+        );
+
+        assert_eq!(
+            orphans,
+            vec![Orphan {
+                target: dropped.path().to_path_buf(),
+                dest: dotfiles.child("dropped").path().to_path_buf(),
+            }]
+        );
+    });
+
+    test_in_temp_dir!(it_should_find_recorded_links_outside_roots, |temp_dir| {
+        let dotfiles = temp_dir.child("dotfiles");
+        dotfiles.child("dropped").touch().unwrap();
+        let outside = temp_dir.child("outside");
+        outside.create_dir_all().unwrap();
+        let dropped = outside.child("dropped");
+        symlink(dotfiles.child("dropped").path(), dropped.path()).unwrap();
+
+        let config = Config {
+            path: dotfiles.child(".ezcfg.toml").path().to_path_buf(),
+            ..Default::default()
+        };
+        let mut state = State::default();
+        state.record(LinkRecord::new(
+            dotfiles.child("dropped").path().to_str().unwrap(),
+            dropped.path().to_str().unwrap(),
+            LinkMode::Symlink,
+            &config.path,
+        ));
+
+        let orphans = find_orphans(&config, dotfiles.path(), &[], 3, &state);
+
+        assert_eq!(orphans.len(), 1);
+        assert_eq!(orphans[0].target, dropped.path());
+    });
+}
@@ -3,7 +3,7 @@ use std::{env::current_dir, path::PathBuf};
 use ezcfg_cli::warn;
 use ezcfg_config::Config;
 
-static CONFIG_FILES: [&str; 2] = [".ezcfg.toml", ".ezcfg/ezcfg.toml"];
+pub static CONFIG_FILES: [&str; 2] = [".ezcfg.toml", ".ezcfg/ezcfg.toml"];
 
 pub fn read_config() -> Option<Config> {
     let config_file_list = CONFIG_FILES
@@ -0,0 +1,198 @@
+use std::{env::current_dir, path::Path};
+
+use clap::ArgMatches;
+use ezcfg_cli::{error, info, warn};
+use ezcfg_config::{resolve_target, ConfigEditor, Section};
+use ezcfg_linker::{FsBackend, LinkBackend};
+use ezcfg_state::State;
+
+use crate::read_config::read_config;
+
+pub fn remove(matches: &ArgMatches) -> Result<(), ()> {
+    let config = read_config().ok_or(())?;
+    let dotfiles = current_dir().unwrap();
+    let path = matches.get_one::<String>("path").unwrap();
+    let sections = match matches.get_one::<String>("section") {
+        Some(section) => vec![Section::try_from(section.as_str()).unwrap()],
+        None => Section::ALL.to_vec(),
+    };
+
+    let removed = remove_from_config(&config.path, &dotfiles, path, &sections)?;
+    if removed.is_empty() {
+        let msg = format!("No link declared with source or target: {}", path);
+        error!(msg);
+        return Err(());
+    }
+
+    if !matches.get_flag("unlink") {
+        return Ok(());
+    }
+
+    let state_path = State::default_path();
+    let mut state = State::load(&state_path).unwrap_or_else(|e| {
+        let msg = format!("Failed to read state file: {:?}", state_path);
+        let raw_err = format!("{:?}", e);
+        warn!(msg, raw_err);
+        State::default()
+    });
+
+    let mut res = Ok(());
+    for (source, target) in removed.iter() {
+        if !FsBackend.is_linked(source, target) {
+            continue;
+        }
+        match FsBackend.remove(target) {
+            Ok(_) => {
+                state.forget(target);
+                let msg = format!("Unlinked: {}", target);
+                info!(msg);
+            }
+            Err(e) => {
+                let msg = format!("Failed to remove link: {}", target);
+                let raw_err = format!("{:?}", e);
+                warn!(msg, raw_err);
+                res = Err(());
+            }
+        }
+    }
+
+    if let Err(e) = state.save(&state_path) {
+        let msg = format!("Failed to write state file: {:?}", state_path);
+        let raw_err = format!("{:?}", e);
+        warn!(msg, raw_err);
+    }
+
+    res
+}
+
+/// Remove the links whose source or target is `path` from `sections` of the config file
+///
+/// The resolved links which have been removed are returned.
+pub fn remove_from_config(
+    config_path: &Path,
+    dotfiles: &Path,
+    path: &str,
+    sections: &[Section],
+) -> Result<Vec<(String, String)>, ()> {
+    let path_abs = dotfiles.join(path);
+    let matches = |source: &str, target: &str| {
+        target == path
+            || Path::new(&resolve_target(target)) == path_abs
+            || dotfiles.join(source) == path_abs
+    };
+
+    let mut editor = ConfigEditor::open(config_path).map_err(|e| {
+        let msg = format!("Failed to edit config file: {:?}", config_path);
+        let raw_err = format!("{:?}", e);
+        error!(msg, raw_err);
+    })?;
+
+    let mut removed = vec![];
+    for section in sections {
+        let links = editor.remove_links(*section, matches).map_err(|e| {
+            let msg = format!("Failed to remove link from section: {}", section);
+            let raw_err = format!("{:?}", e);
+            error!(msg, raw_err);
+        })?;
+
+        for (source, target) in links {
+            let msg = format!("Removed from section {}: {} -> {}", section, source, target);
+            info!(msg);
+            removed.push((
+                dotfiles.join(&source).to_str().unwrap().to_string(),
+                resolve_target(&target),
+            ));
+        }
+    }
+
+    if !removed.is_empty() {
+        editor.save().map_err(|e| {
+            let msg = format!("Failed to write config file: {:?}", config_path);
+            let raw_err = format!("{:?}", e);
+            error!(msg, raw_err);
+        })?;
+    }
+
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::read_to_string;
+
+    use assert_fs::prelude::{FileWriteStr, PathChild};
+    use ezcfg_test::test_in_temp_dir;
+
+    use super::*;
+
+    const CONFIG: &str = r#"links = [
+    # shell
+    ["zsh/.zshrc", "/home/.zshrc"],
+    # editor
+    { source = "nvim", target = "/home/.config/nvim" },
+]
+
+linux = [
+    ["zsh/.zshrc", "/home/.zshrc"], # same on linux
+]
+"#;
+
+    test_in_temp_dir!(it_should_remove_by_target_from_every_section, |temp_dir| {
+        let config = temp_dir.child(".ezcfg.toml");
+        config.write_str(CONFIG).unwrap();
+
+        let removed = remove_from_config(
+            config.path(),
+            temp_dir.path(),
+            "/home/.zshrc",
+            &Section::ALL,
+        )
+        .unwrap();
+
+        let source = temp_dir
+            .child("zsh/.zshrc")
+            .path()
+            .to_str()
+            .unwrap()
+            .to_string();
+        assert_eq!(
+            removed,
+            vec![
+                (source.clone(), "/home/.zshrc".to_string()),
+                (source, "/home/.zshrc".to_string())
+            ]
+        );
+        assert_eq!(
+            read_to_string(config.path()).unwrap(),
+            r#"links = [
+    # shell
+    # editor
+    { source = "nvim", target = "/home/.config/nvim" },
+]
+
+linux = [
+]
+"#
+        );
+    });
+
+    test_in_temp_dir!(it_should_remove_by_source_from_given_section, |temp_dir| {
+        let config = temp_dir.child(".ezcfg.toml");
+        config.write_str(CONFIG).unwrap();
+
+        let removed =
+            remove_from_config(config.path(), temp_dir.path(), "nvim", &[Section::Links]).unwrap();
+
+        assert_eq!(removed.len(), 1);
+        assert!(!read_to_string(config.path()).unwrap().contains("nvim"));
+
+        let removed = remove_from_config(
+            config.path(),
+            temp_dir.path(),
+            "zsh/.zshrc",
+            &[Section::Macos],
+        )
+        .unwrap();
+        assert!(removed.is_empty());
+    });
+}
@@ -0,0 +1,193 @@
+use std::{
+    env,
+    fs::{create_dir_all, read_to_string, OpenOptions},
+    io::Write,
+    os::unix::fs::OpenOptionsExt,
+    path::{Path, PathBuf},
+};
+
+use age::{
+    armor::ArmoredReader,
+    scrypt,
+    secrecy::{ExposeSecret, SecretString},
+    x25519, DecryptError, Decryptor, EncryptError,
+};
+use ezcfg_cli::{error, info};
+
+/// Where the age identity used for secrets is kept
+///
+/// `$EZCFG_IDENTITY`, `$XDG_CONFIG_HOME/ezcfg/identity.txt`
+/// or `$HOME/.config/ezcfg/identity.txt`.
+pub fn identity_path() -> PathBuf {
+    if let Some(path) = env::var_os("EZCFG_IDENTITY").filter(|path| !path.is_empty()) {
+        return PathBuf::from(path);
+    }
+    let config_home = env::var_os("XDG_CONFIG_HOME")
+        .filter(|path| !path.is_empty())
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(env::var("HOME").unwrap()).join(".config"));
+    config_home.join("ezcfg").join("identity.txt")
+}
+
+/// What a secret is encrypted with
+pub enum Key {
+    Identity(Box<x25519::Identity>),
+    Passphrase(SecretString),
+}
+
+pub fn encrypt(plaintext: &[u8], key: &Key) -> Result<Vec<u8>, EncryptError> {
+    let ciphertext = match key {
+        Key::Identity(identity) => age::encrypt_and_armor(&identity.to_public(), plaintext),
+        Key::Passphrase(passphrase) => {
+            age::encrypt_and_armor(&scrypt::Recipient::new(passphrase.clone()), plaintext)
+        }
+    };
+    ciphertext.map(String::into_bytes)
+}
+
+pub fn decrypt(ciphertext: &[u8], key: &Key) -> Result<Vec<u8>, DecryptError> {
+    match key {
+        Key::Identity(identity) => age::decrypt(identity.as_ref(), ciphertext),
+        Key::Passphrase(passphrase) => {
+            age::decrypt(&scrypt::Identity::new(passphrase.clone()), ciphertext)
+        }
+    }
+}
+
+/// Whether `ciphertext` has been encrypted with a passphrase rather than an identity
+pub fn is_passphrase_protected(ciphertext: &[u8]) -> bool {
+    Decryptor::new_buffered(ArmoredReader::new(ciphertext))
+        .is_ok_and(|decryptor| decryptor.is_scrypt())
+}
+
+/// Read the first identity of the identity file at `path`
+pub fn load_identity(path: &Path) -> Result<x25519::Identity, ()> {
+    let content = read_to_string(path).map_err(|e| {
+        let msg = format!("Failed to read identity file: {:?}", path);
+        let raw_err = format!("{:?}", e);
+        error!(msg, raw_err);
+    })?;
+
+    content
+        .lines()
+        .map(|line| line.trim())
+        .find(|line| !line.is_empty() && !line.starts_with('#'))
+        .and_then(|line| line.parse().ok())
+        .ok_or_else(|| {
+            let msg = format!("No valid identity found in: {:?}", path);
+            error!(msg);
+        })
+}
+
+/// Create a new identity file at `path`, readable by the owner only
+pub fn generate_identity(path: &Path) -> Result<x25519::Identity, ()> {
+    let identity = x25519::Identity::generate();
+    let content = format!(
+        "# public key: {}\n{}\n",
+        identity.to_public(),
+        identity.to_string().expose_secret()
+    );
+
+    let res = path.parent().map_or(Ok(()), create_dir_all).and_then(|_| {
+        OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .mode(0o600)
+            .open(path)?
+            .write_all(content.as_bytes())
+    });
+    if let Err(e) = res {
+        let msg = format!("Failed to write identity file: {:?}", path);
+        let raw_err = format!("{:?}", e);
+        error!(msg, raw_err);
+        return Err(());
+    }
+
+    let msg = format!("Generated a new identity, keep a backup of it: {:?}", path);
+    info!(msg);
+    Ok(identity)
+}
+
+/// The keys of a run, each one is loaded or asked for once, when it is first needed
+pub struct Keyring<F> {
+    identity_path: PathBuf,
+    identity: Option<Key>,
+    passphrase: Option<Key>,
+    ask: F,
+}
+
+impl<F: FnMut(&str) -> String> Keyring<F> {
+    /// Use the identity file at `identity_path`, and ask for the passphrase with `ask`
+    pub fn new(identity_path: PathBuf, ask: F) -> Self {
+        Keyring {
+            identity_path,
+            identity: None,
+            passphrase: None,
+            ask,
+        }
+    }
+
+    /// The key which `ciphertext` may be decrypted with
+    pub fn key_for(&mut self, ciphertext: &[u8]) -> Result<&Key, ()> {
+        if is_passphrase_protected(ciphertext) {
+            let ask = &mut self.ask;
+            let passphrase = self
+                .passphrase
+                .get_or_insert_with(|| Key::Passphrase(ask("Passphrase of the secrets: ").into()));
+            return Ok(passphrase);
+        }
+
+        if self.identity.is_none() {
+            let identity = load_identity(&self.identity_path)?;
+            self.identity = Some(Key::Identity(Box::new(identity)));
+        }
+        Ok(self.identity.as_ref().unwrap())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs::metadata, os::unix::fs::PermissionsExt};
+
+    use assert_fs::prelude::PathChild;
+    use ezcfg_test::test_in_temp_dir;
+
+    use super::*;
+
+    test_in_temp_dir!(it_should_round_trip_with_a_generated_identity, |temp_dir| {
+        let path = temp_dir.child("ezcfg/identity.txt");
+        let identity = generate_identity(path.path()).unwrap();
+        assert_eq!(
+            metadata(path.path()).unwrap().permissions().mode() & 0o777,
+            0o600
+        );
+        assert!(generate_identity(path.path()).is_err());
+
+        let ciphertext = encrypt(b"secret", &Key::Identity(Box::new(identity))).unwrap();
+        assert!(ciphertext.starts_with(b"-----BEGIN AGE ENCRYPTED FILE-----"));
+        assert!(!is_passphrase_protected(&ciphertext));
+
+        let mut keyring = Keyring::new(path.path().to_path_buf(), |_: &str| unreachable!());
+        let key = keyring.key_for(&ciphertext).unwrap();
+        assert_eq!(decrypt(&ciphertext, key).unwrap(), b"secret");
+    });
+
+    #[test]
+    fn it_should_ask_for_the_passphrase_once() {
+        let key = Key::Passphrase("correct horse".into());
+        let ciphertext = encrypt(b"secret", &key).unwrap();
+        assert!(is_passphrase_protected(&ciphertext));
+
+        let mut asked = 0;
+        let mut keyring = Keyring::new(PathBuf::new(), |_: &str| {
+            asked += 1;
+            "correct horse".to_string()
+        });
+        for _ in 0..2 {
+            let key = keyring.key_for(&ciphertext).unwrap();
+            assert_eq!(decrypt(&ciphertext, key).unwrap(), b"secret");
+        }
+        drop(keyring);
+        assert_eq!(asked, 1);
+    }
+}
@@ -0,0 +1,23 @@
+use std::env::current_dir;
+
+use ezcfg_cli::error;
+use ezcfg_config::{order_links, Config};
+
+/// Check that the links of `config` do not overlap, and order them so that parent links come first
+///
+/// Each problem found is reported, and nothing is returned if there is any.
+pub fn validate_config(mut config: Config) -> Option<Config> {
+    match order_links(&config.links, &current_dir().unwrap()) {
+        Ok(links) => {
+            config.links = links;
+            Some(config)
+        }
+        Err(errors) => {
+            for e in errors {
+                let msg = format!("Invalid links: {}", e);
+                error!(msg);
+            }
+            None
+        }
+    }
+}
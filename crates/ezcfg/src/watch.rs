@@ -0,0 +1,249 @@
+use std::{env::current_dir, path::Path, sync::mpsc::channel, time::Duration};
+
+use clap::ArgMatches;
+use ezcfg_cli::{error, info, warn};
+use ezcfg_config::{Config, Links};
+use ezcfg_linker::{Entry, FsBackend, LinkBackend};
+use ezcfg_state::State;
+use notify::{recommended_watcher, Event, RecursiveMode, Watcher};
+
+use crate::{
+    link_all_with_config::{link_all_with_config, LinkFlags},
+    read_config::read_config,
+    validate_config::validate_config,
+    write_state::write_state,
+};
+
+pub fn watch(matches: &ArgMatches) -> Result<(), ()> {
+    let debounce = Duration::from_millis(*matches.get_one::<u64>("debounce").unwrap());
+    let dotfiles = current_dir().unwrap();
+    let mut config = read_config().and_then(validate_config).ok_or(())?;
+
+    let (tx, rx) = channel();
+    let mut watcher = recommended_watcher(tx).map_err(|e| {
+        let raw_err = format!("{:?}", e);
+        error!("Failed to start watching for changes", raw_err);
+    })?;
+    watcher
+        .watch(&dotfiles, RecursiveMode::Recursive)
+        .map_err(|e| {
+            let msg = format!("Failed to watch directory: {:?}", dotfiles);
+            let raw_err = format!("{:?}", e);
+            error!(msg, raw_err);
+        })?;
+
+    apply(&delta(&[], &config.links, &FsBackend), &config);
+    let msg = format!("Watching {:?} for changes, press Ctrl-C to stop", dotfiles);
+    info!(msg);
+
+    while let Ok(event) = rx.recv() {
+        // Wait for the changes to settle, editors tend to write a file several times
+        let mut changed = is_relevant(&event, &dotfiles);
+        while let Ok(event) = rx.recv_timeout(debounce) {
+            changed |= is_relevant(&event, &dotfiles);
+        }
+        if !changed {
+            continue;
+        }
+
+        let Some(new_config) = read_config().and_then(validate_config) else {
+            warn!("Keep watching with the previous config");
+            continue;
+        };
+        apply(
+            &delta(&config.links, &new_config.links, &FsBackend),
+            &new_config,
+        );
+        config = new_config;
+    }
+
+    Ok(())
+}
+
+/// What has to be done to go from the links of one config to the links of another
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Delta {
+    /// The links to create
+    pub link: Links,
+    /// The links to remove, only those which are in place
+    pub unlink: Links,
+}
+
+impl Delta {
+    pub fn is_empty(&self) -> bool {
+        self.link.is_empty() && self.unlink.is_empty()
+    }
+}
+
+/// Compare the links of `old` and `new`
+///
+/// Besides the links which have been added, the links which were declared before
+/// but whose source has only appeared since, or whose target has gone, are linked again.
+pub fn delta<B: LinkBackend>(
+    old: &[(String, String)],
+    new: &[(String, String)],
+    backend: &B,
+) -> Delta {
+    let is_missing = |path: &str| matches!(backend.inspect(path), Ok(Entry::Missing));
+
+    let link = new
+        .iter()
+        .filter(|link| {
+            let (source, target) = link;
+            !old.contains(link) || (!is_missing(source) && is_missing(target))
+        })
+        .cloned()
+        .collect();
+    let unlink = old
+        .iter()
+        .filter(|link| !new.contains(link) && backend.is_linked(&link.0, &link.1))
+        .cloned()
+        .collect();
+
+    Delta { link, unlink }
+}
+
+fn apply(delta: &Delta, config: &Config) {
+    if delta.is_empty() {
+        return;
+    }
+    let msg = format!(
+        "Applying changes: {} link(s) to create, {} link(s) to remove",
+        delta.link.len(),
+        delta.unlink.len()
+    );
+    info!(msg);
+
+    if !delta.unlink.is_empty() {
+        unlink_all(&delta.unlink);
+    }
+    if !delta.link.is_empty() {
+        let config = Config {
+            links: delta.link.clone(),
+            ..config.clone()
+        };
+        let report = link_all_with_config(&config, FsBackend, LinkFlags::default());
+        let msg = format!("{}", report);
+        info!(msg);
+        write_state(&config);
+    }
+}
+
+fn unlink_all(links: &[(String, String)]) {
+    let state_path = State::default_path();
+    let mut state = State::load(&state_path).unwrap_or_else(|e| {
+        let msg = format!("Failed to read state file: {:?}", state_path);
+        let raw_err = format!("{:?}", e);
+        warn!(msg, raw_err);
+        State::default()
+    });
+
+    for (_, target) in links {
+        match FsBackend.remove(target) {
+            Ok(_) => {
+                state.forget(target);
+                let msg = format!("Unlinked: {}", target);
+                info!(msg);
+            }
+            Err(e) => {
+                let msg = format!("Failed to remove link: {}", target);
+                let raw_err = format!("{:?}", e);
+                warn!(msg, raw_err);
+            }
+        }
+    }
+
+    if let Err(e) = state.save(&state_path) {
+        let msg = format!("Failed to write state file: {:?}", state_path);
+        let raw_err = format!("{:?}", e);
+        warn!(msg, raw_err);
+    }
+}
+
+/// Whether `event` may change the links, reading files and the git directory are ignored
+fn is_relevant(event: &notify::Result<Event>, dotfiles: &Path) -> bool {
+    let git_dir = dotfiles.join(".git");
+    match event {
+        Ok(event) => {
+            !event.kind.is_access() && event.paths.iter().any(|path| !path.starts_with(&git_dir))
+        }
+        Err(_) => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use ezcfg_linker::MemoryBackend;
+    use notify::{
+        event::{AccessKind, CreateKind},
+        EventKind,
+    };
+
+    use super::*;
+
+    fn link(source: &str, target: &str) -> (String, String) {
+        (source.to_string(), target.to_string())
+    }
+
+    #[test]
+    fn it_should_only_apply_the_delta() {
+        let backend = MemoryBackend::new()
+            .with_file("/dotfiles/kept")
+            .with_symlink("/home/kept", "/dotfiles/kept")
+            .with_file("/dotfiles/removed")
+            .with_symlink("/home/removed", "/dotfiles/removed")
+            .with_file("/dotfiles/appeared")
+            .with_file("/dotfiles/added");
+        let old = vec![
+            link("/dotfiles/kept", "/home/kept"),
+            link("/dotfiles/removed", "/home/removed"),
+            link("/dotfiles/conflict", "/home/conflict"),
+            link("/dotfiles/appeared", "/home/appeared"),
+        ];
+        let new = vec![
+            link("/dotfiles/kept", "/home/kept"),
+            link("/dotfiles/appeared", "/home/appeared"),
+            link("/dotfiles/added", "/home/added"),
+        ];
+
+        assert_eq!(
+            delta(&old, &new, &backend),
+            Delta {
+                link: vec![
+                    link("/dotfiles/appeared", "/home/appeared"),
+                    link("/dotfiles/added", "/home/added"),
+                ],
+                unlink: vec![link("/dotfiles/removed", "/home/removed")],
+            }
+        );
+
+        // Nothing changed in the config, but a target has gone
+        let backend = backend.with_symlink("/home/appeared", "/dotfiles/appeared");
+        assert_eq!(
+            delta(&new, &new, &backend).link,
+            vec![link("/dotfiles/added", "/home/added")]
+        );
+    }
+
+    #[test]
+    fn it_should_ignore_access_and_git_events() {
+        let dotfiles = Path::new("/dotfiles");
+        let event =
+            |kind: EventKind, path: &str| Ok(Event::new(kind).add_path(PathBuf::from(path)));
+
+        assert!(is_relevant(
+            &event(EventKind::Create(CreateKind::File), "/dotfiles/new"),
+            dotfiles
+        ));
+        assert!(!is_relevant(
+            &event(EventKind::Create(CreateKind::File), "/dotfiles/.git/index"),
+            dotfiles
+        ));
+        assert!(!is_relevant(
+            &event(EventKind::Access(AccessKind::Any), "/dotfiles/.ezcfg.toml"),
+            dotfiles
+        ));
+    }
+}
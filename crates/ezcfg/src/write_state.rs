@@ -0,0 +1,90 @@
+use std::path::Path;
+
+use ezcfg_cli::warn;
+use ezcfg_config::Config;
+use ezcfg_linker::{FsBackend, LinkBackend};
+use ezcfg_state::{LinkMode, LinkRecord, State};
+
+/// Remember every link of `config` which is in place after a run, and every decrypted secret
+pub fn write_state(config: &Config) {
+    write_state_to(config, &State::default_path())
+}
+
+/// How the link from `source` to `target` is in place, if it is:
+/// a decrypted file for secrets, a soft link otherwise
+pub fn link_mode(config: &Config, source: &str, target: &str) -> Option<LinkMode> {
+    match config.is_encrypted(target) {
+        true if Path::new(target).is_file() => Some(LinkMode::Encrypted),
+        false if FsBackend.is_linked(source, target) => Some(LinkMode::Symlink),
+        _ => None,
+    }
+}
+
+fn write_state_to(config: &Config, state_path: &Path) {
+    let mut state = match State::load(state_path) {
+        Ok(state) => state,
+        Err(e) => {
+            let msg = format!("Failed to read state file: {:?}", state_path);
+            let raw_err = format!("{:?}", e);
+            warn!(msg, raw_err);
+            return;
+        }
+    };
+
+    for (source, target) in config.links.iter() {
+        if let Some(mode) = link_mode(config, source, target) {
+            state.record(LinkRecord::new(source, target, mode, &config.path));
+        }
+    }
+
+    if let Err(e) = state.save(state_path) {
+        let msg = format!("Failed to write state file: {:?}", state_path);
+        let raw_err = format!("{:?}", e);
+        warn!(msg, raw_err);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::os::unix::fs::symlink;
+
+    use assert_fs::prelude::{FileTouch, PathChild};
+    use ezcfg_test::test_in_temp_dir;
+
+    use super::*;
+
+    test_in_temp_dir!(it_should_record_links_in_place, |temp_dir| {
+        let source = temp_dir.child("source");
+        source.touch().unwrap();
+        let linked = temp_dir.child("linked");
+        symlink(source.path(), linked.path()).unwrap();
+
+        let source_path = source.path().to_str().unwrap().to_string();
+        let linked_path = linked.path().to_str().unwrap().to_string();
+        let missing_path = temp_dir
+            .child("missing")
+            .path()
+            .to_str()
+            .unwrap()
+            .to_string();
+
+        let mut config = Config::new(
+            vec![
+                (source_path.clone(), linked_path.clone()),
+                (source_path.clone(), missing_path.clone()),
+            ],
+            false,
+        );
+        config.path = temp_dir.child(".ezcfg.toml").path().to_path_buf();
+        let state_path = temp_dir.child("state/state.json");
+
+        write_state_to(&config, state_path.path());
+
+        let state = State::load(state_path.path()).unwrap();
+        let record = state.get(&linked_path).unwrap();
+        assert_eq!(record.source, source_path);
+        assert_eq!(record.mode, LinkMode::Symlink);
+        assert_eq!(record.config, config.path);
+        assert!(!state.owns(&missing_path));
+    });
+}
@@ -0,0 +1,126 @@
+//! Compare linking with the thread pool against creating the links one by one.
+//!
+//! The sequential baseline calls the backend in a loop, without the linker.
+//! The pools are started before measuring, so their threads are not counted.
+//!
+//! The links are created on tmpfs when `/dev/shm` exists,
+//! so the results measure the linker rather than the disk.
+//!
+//! Run with `cargo bench -p ezcfg_linker`.
+
+use std::{
+    env::temp_dir,
+    fs::{create_dir_all, remove_dir_all, write},
+    path::{Path, PathBuf},
+    process,
+};
+
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput};
+use ezcfg_linker::{FsBackend, LinkBackend, Linker, SilentSink};
+
+/// Link sets small enough to fit a typical dotfiles repository, and large enough to stress the pool
+const SIZES: [usize; 2] = [100, 10_000];
+
+/// Worker counts to compare, `None` being the global pool with one thread per CPU,
+/// and a single job linking on the calling thread
+const JOBS: [Option<usize>; 5] = [Some(1), Some(2), Some(4), Some(8), None];
+
+/// A directory removed once the benchmark is over
+struct Scratch(PathBuf);
+
+impl Scratch {
+    fn new() -> Self {
+        let shm = Path::new("/dev/shm");
+        let root = match shm.is_dir() {
+            true => shm.to_path_buf(),
+            false => temp_dir(),
+        };
+        let path = root.join(format!("ezcfg-bench-{}", process::id()));
+        create_dir_all(&path).unwrap();
+        Scratch(path)
+    }
+}
+
+impl Drop for Scratch {
+    fn drop(&mut self) {
+        let _ = remove_dir_all(&self.0);
+    }
+}
+
+/// Create `size` sources, and return the links to targets in `targets`
+fn prepare(scratch: &Scratch, size: usize) -> (PathBuf, Vec<(String, String)>) {
+    let sources = scratch.0.join(format!("sources-{}", size));
+    let targets = scratch.0.join(format!("targets-{}", size));
+    create_dir_all(&sources).unwrap();
+
+    let links = (0..size)
+        .map(|i| {
+            let source = sources.join(format!("{}.txt", i));
+            write(&source, i.to_string()).unwrap();
+            let target = targets.join(i.to_string());
+            (
+                source.to_string_lossy().to_string(),
+                target.to_string_lossy().to_string(),
+            )
+        })
+        .collect();
+
+    (targets, links)
+}
+
+fn reset(targets: &Path) {
+    let _ = remove_dir_all(targets);
+    create_dir_all(targets).unwrap();
+}
+
+fn bench_link_all(c: &mut Criterion) {
+    let scratch = Scratch::new();
+    let mut group = c.benchmark_group("link_all");
+    group.sample_size(20);
+
+    for size in SIZES {
+        let (targets, links) = prepare(&scratch, size);
+        group.throughput(Throughput::Elements(size as u64));
+
+        let id = BenchmarkId::new("sequential", size);
+        group.bench_with_input(id, &links, |b, links| {
+            b.iter_batched(
+                || reset(&targets),
+                |_| {
+                    for (source, target) in links {
+                        FsBackend.create(source, target).unwrap();
+                    }
+                },
+                BatchSize::PerIteration,
+            )
+        });
+
+        for jobs in JOBS {
+            let (id, linker) = match jobs {
+                Some(jobs) => (
+                    BenchmarkId::new(format!("{}_jobs", jobs), size),
+                    Linker::new().with_sink(SilentSink).with_jobs(jobs),
+                ),
+                None => (
+                    BenchmarkId::new("global_pool", size),
+                    Linker::new().with_sink(SilentSink),
+                ),
+            };
+            group.bench_with_input(id, &links, |b, links| {
+                b.iter_batched(
+                    || reset(&targets),
+                    |_| {
+                        let report = linker.link_all(links);
+                        assert_eq!(report.linked, links.len());
+                    },
+                    BatchSize::PerIteration,
+                )
+            });
+        }
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_link_all);
+criterion_main!(benches);
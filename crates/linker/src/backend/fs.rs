@@ -0,0 +1,197 @@
+use std::{
+    fs::{create_dir_all, read_link, remove_dir_all, remove_file, rename, symlink_metadata},
+    io::{Error as IoError, ErrorKind as IoErrorKind},
+    path::Path,
+};
+
+use super::{Entry, LinkBackend};
+
+/// The backend operating on the real filesystem
+#[derive(Debug, Default, Clone, Copy)]
+pub struct FsBackend;
+
+impl LinkBackend for FsBackend {
+    fn create(&self, source: &str, target: &str) -> Result<(), IoError> {
+        soft_link(source, target)
+    }
+
+    fn remove(&self, target: &str) -> Result<(), IoError> {
+        let metadata = symlink_metadata(target)?;
+        if metadata.is_dir() {
+            remove_dir_all(target)
+        } else {
+            remove_file(target)
+        }
+    }
+
+    fn rename(&self, from: &str, to: &str) -> Result<(), IoError> {
+        rename(from, to)
+    }
+
+    fn write(&self, path: &str, contents: &[u8], mode: u32) -> Result<(), IoError> {
+        if let Some(parent) = Path::new(path).parent() {
+            create_dir_all(parent)?;
+        }
+        write_new(path, contents, mode)
+    }
+
+    fn mode(&self, path: &str) -> Result<u32, IoError> {
+        get_mode(path)
+    }
+
+    fn set_mode(&self, path: &str, mode: u32) -> Result<(), IoError> {
+        set_mode(path, mode)
+    }
+
+    fn inspect(&self, path: &str) -> Result<Entry, IoError> {
+        let metadata = match symlink_metadata(path) {
+            Ok(metadata) => metadata,
+            Err(e) if e.kind() == IoErrorKind::NotFound => return Ok(Entry::Missing),
+            Err(e) => return Err(e),
+        };
+
+        let file_type = metadata.file_type();
+        if file_type.is_symlink() {
+            let dest = read_link(path)?;
+            Ok(Entry::Symlink(dest.to_string_lossy().to_string()))
+        } else if file_type.is_dir() {
+            Ok(Entry::Dir)
+        } else {
+            Ok(Entry::File)
+        }
+    }
+}
+
+#[cfg(target_family = "unix")]
+fn soft_link(source: &str, target: &str) -> Result<(), IoError> {
+    std::os::unix::fs::symlink(source, target)
+}
+
+#[cfg(target_family = "unix")]
+fn write_new(path: &str, contents: &[u8], mode: u32) -> Result<(), IoError> {
+    use std::{
+        fs::{set_permissions, OpenOptions, Permissions},
+        io::Write,
+        os::unix::fs::{OpenOptionsExt, PermissionsExt},
+    };
+
+    OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(mode)
+        .open(path)?
+        .write_all(contents)?;
+    // The mode given to `open` is masked by the umask
+    set_permissions(path, Permissions::from_mode(mode))
+}
+
+#[cfg(target_family = "unix")]
+fn get_mode(path: &str) -> Result<u32, IoError> {
+    use std::{fs::metadata, os::unix::fs::PermissionsExt};
+
+    Ok(metadata(path)?.permissions().mode() & 0o7777)
+}
+
+#[cfg(target_family = "unix")]
+fn set_mode(path: &str, mode: u32) -> Result<(), IoError> {
+    use std::{
+        fs::{set_permissions, Permissions},
+        os::unix::fs::PermissionsExt,
+    };
+
+    set_permissions(path, Permissions::from_mode(mode))
+}
+
+#[cfg(not(target_family = "unix"))]
+fn write_new(path: &str, contents: &[u8], _mode: u32) -> Result<(), IoError> {
+    use std::{fs::OpenOptions, io::Write};
+
+    OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(path)?
+        .write_all(contents)
+}
+
+#[cfg(not(target_family = "unix"))]
+fn get_mode(_path: &str) -> Result<u32, IoError> {
+    Err(IoError::other(format!(
+        "Unsupported OS: {}",
+        std::env::consts::OS
+    )))
+}
+
+#[cfg(not(target_family = "unix"))]
+fn set_mode(_path: &str, _mode: u32) -> Result<(), IoError> {
+    Err(IoError::other(format!(
+        "Unsupported OS: {}",
+        std::env::consts::OS
+    )))
+}
+
+#[cfg(not(target_family = "unix"))]
+fn soft_link(_source: &str, _target: &str) -> Result<(), IoError> {
+    Err(IoError::other(format!(
+        "Unsupported OS: {}",
+        std::env::consts::OS
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_fs::{prelude::*, TempDir};
+
+    use super::*;
+
+    #[test]
+    fn it_should_create_and_inspect_soft_link() {
+        let temp_dir = TempDir::new().unwrap();
+        let source = temp_dir.child("source");
+        source.touch().unwrap();
+        let target = temp_dir.child("target");
+
+        let source_path = source.path().to_str().unwrap();
+        let target_path = target.path().to_str().unwrap();
+
+        assert_eq!(FsBackend.inspect(target_path).unwrap(), Entry::Missing);
+        FsBackend.create(source_path, target_path).unwrap();
+        assert_eq!(
+            FsBackend.inspect(target_path).unwrap(),
+            Entry::Symlink(source_path.to_string())
+        );
+        assert_eq!(FsBackend.inspect(source_path).unwrap(), Entry::File);
+    }
+
+    #[test]
+    fn it_should_set_mode_through_soft_link() {
+        let temp_dir = TempDir::new().unwrap();
+        let source = temp_dir.child("source");
+        source.touch().unwrap();
+        let target = temp_dir.child("target");
+        target.symlink_to_file(source.path()).unwrap();
+
+        let source_path = source.path().to_str().unwrap();
+        let target_path = target.path().to_str().unwrap();
+
+        FsBackend.set_mode(target_path, 0o600).unwrap();
+        assert_eq!(FsBackend.mode(source_path).unwrap(), 0o600);
+        assert_eq!(FsBackend.mode(target_path).unwrap(), 0o600);
+    }
+
+    #[test]
+    fn it_should_remove_file_dir_and_soft_link() {
+        let temp_dir = TempDir::new().unwrap();
+        let file = temp_dir.child("file");
+        file.touch().unwrap();
+        let dir = temp_dir.child("dir");
+        dir.child("nested").touch().unwrap();
+        let link = temp_dir.child("link");
+        link.symlink_to_dir(dir.path()).unwrap();
+
+        for path in [link.path(), dir.path(), file.path()] {
+            let path = path.to_str().unwrap();
+            FsBackend.remove(path).unwrap();
+            assert_eq!(FsBackend.inspect(path).unwrap(), Entry::Missing);
+        }
+    }
+}
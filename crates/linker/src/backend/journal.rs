@@ -0,0 +1,383 @@
+use std::{
+    io::Error as IoError,
+    path::Path,
+    process,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Mutex,
+    },
+};
+
+use super::{Entry, FsBackend, LinkBackend};
+
+/// A modifying operation applied by [`JournalBackend`], in a way it can be undone
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JournalEntry {
+    /// A soft link has been created at `target`
+    Created {
+        target: String,
+    },
+    /// `target` has been moved aside to `stash` instead of being removed
+    MovedAside {
+        target: String,
+        stash: String,
+    },
+    Renamed {
+        from: String,
+        to: String,
+    },
+    /// A file has been written at `path`, along with its missing parent directories
+    /// `new_dirs`, the outermost first
+    Written {
+        path: String,
+        new_dirs: Vec<String>,
+    },
+    /// The permissions of `path` have been changed, they were `mode` before
+    ModeChanged {
+        path: String,
+        mode: u32,
+    },
+}
+
+/// A backend for transactional linking
+///
+/// Every modification is recorded in an undo journal, and removed entries are
+/// moved aside next to their original location rather than deleted.
+/// Once the run is over, either [`JournalBackend::commit`] the changes
+/// or [`JournalBackend::rollback`] all of them.
+///
+/// # Example
+///
+/// ```rust
+/// use ezcfg_linker::{Entry, JournalBackend, LinkBackend, MemoryBackend};
+///
+/// let backend = JournalBackend::wrap(MemoryBackend::new().with_file("/home/.zshrc"));
+/// backend.remove("/home/.zshrc").unwrap();
+/// backend.create("/dotfiles/.zshrc", "/home/.zshrc").unwrap();
+///
+/// backend.rollback().unwrap();
+/// assert_eq!(backend.inspect("/home/.zshrc").unwrap(), Entry::File);
+/// ```
+#[derive(Debug, Default)]
+pub struct JournalBackend<B = FsBackend> {
+    inner: B,
+    journal: Mutex<Vec<JournalEntry>>,
+    failed: AtomicBool,
+    stash_count: AtomicUsize,
+}
+
+impl JournalBackend {
+    /// Journal operations on the real filesystem
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<B: LinkBackend> JournalBackend<B> {
+    /// Journal operations on top of another backend
+    pub fn wrap(inner: B) -> Self {
+        JournalBackend {
+            inner,
+            journal: Mutex::new(vec![]),
+            failed: AtomicBool::new(false),
+            stash_count: AtomicUsize::new(0),
+        }
+    }
+
+    /// Get the undo journal in the order the operations were applied
+    pub fn journal(&self) -> Vec<JournalEntry> {
+        self.journal.lock().unwrap().clone()
+    }
+
+    /// Whether any soft link or file failed to be created
+    pub fn has_failed(&self) -> bool {
+        self.failed.load(Ordering::SeqCst)
+    }
+
+    /// Make the changes permanent by deleting the entries moved aside
+    pub fn commit(&self) -> Result<(), IoError> {
+        let journal = std::mem::take(&mut *self.journal.lock().unwrap());
+
+        journal.iter().try_for_each(|entry| match entry {
+            JournalEntry::MovedAside { stash, .. } => self.inner.remove(stash),
+            _ => Ok(()),
+        })
+    }
+
+    /// Undo every operation in reverse order
+    ///
+    /// It keeps going when an operation can not be undone,
+    /// and returns the first error encountered.
+    /// Operations which could not be undone are kept in the journal.
+    pub fn rollback(&self) -> Result<(), IoError> {
+        let journal = std::mem::take(&mut *self.journal.lock().unwrap());
+
+        let mut res = Ok(());
+        let mut leftover = vec![];
+        for entry in journal.into_iter().rev() {
+            let undo = match &entry {
+                JournalEntry::Created { target } => self.inner.remove(target),
+                JournalEntry::MovedAside { target, stash } => self.inner.rename(stash, target),
+                JournalEntry::Renamed { from, to } => self.inner.rename(to, from),
+                JournalEntry::Written { path, new_dirs } => {
+                    self.inner.remove(path).and_then(|_| {
+                        new_dirs
+                            .iter()
+                            .rev()
+                            .try_for_each(|dir| self.inner.remove(dir))
+                    })
+                }
+                JournalEntry::ModeChanged { path, mode } => self.inner.set_mode(path, *mode),
+            };
+            if undo.is_err() {
+                leftover.insert(0, entry);
+            }
+            res = res.and(undo);
+        }
+
+        *self.journal.lock().unwrap() = leftover;
+        res
+    }
+
+    fn record(&self, entry: JournalEntry) {
+        self.journal.lock().unwrap().push(entry);
+    }
+
+    /// A sibling of `target`, so that moving it aside never crosses filesystems
+    fn stash_path(&self, target: &str) -> String {
+        let count = self.stash_count.fetch_add(1, Ordering::SeqCst);
+        format!(
+            "{}.ezcfg-stash-{}-{}",
+            target.trim_end_matches('/'),
+            process::id(),
+            count
+        )
+    }
+}
+
+impl<B: LinkBackend> LinkBackend for JournalBackend<B> {
+    fn create(&self, source: &str, target: &str) -> Result<(), IoError> {
+        match self.inner.create(source, target) {
+            Ok(_) => {
+                self.record(JournalEntry::Created {
+                    target: target.to_string(),
+                });
+                Ok(())
+            }
+            Err(e) => {
+                self.failed.store(true, Ordering::SeqCst);
+                Err(e)
+            }
+        }
+    }
+
+    fn remove(&self, target: &str) -> Result<(), IoError> {
+        let stash = self.stash_path(target);
+        self.inner.rename(target, &stash)?;
+        self.record(JournalEntry::MovedAside {
+            target: target.to_string(),
+            stash,
+        });
+        Ok(())
+    }
+
+    fn rename(&self, from: &str, to: &str) -> Result<(), IoError> {
+        self.inner.rename(from, to)?;
+        self.record(JournalEntry::Renamed {
+            from: from.to_string(),
+            to: to.to_string(),
+        });
+        Ok(())
+    }
+
+    fn write(&self, path: &str, contents: &[u8], mode: u32) -> Result<(), IoError> {
+        let mut new_dirs = Path::new(path)
+            .ancestors()
+            .skip(1)
+            .map(|dir| dir.to_str().unwrap())
+            .filter(|dir| !dir.is_empty())
+            .take_while(|dir| matches!(self.inner.inspect(dir), Ok(Entry::Missing)))
+            .map(str::to_string)
+            .collect::<Vec<_>>();
+        new_dirs.reverse();
+
+        if let Err(e) = self.inner.write(path, contents, mode) {
+            self.failed.store(true, Ordering::SeqCst);
+            return Err(e);
+        }
+        self.record(JournalEntry::Written {
+            path: path.to_string(),
+            new_dirs,
+        });
+        Ok(())
+    }
+
+    fn mode(&self, path: &str) -> Result<u32, IoError> {
+        self.inner.mode(path)
+    }
+
+    fn set_mode(&self, path: &str, mode: u32) -> Result<(), IoError> {
+        let previous = self.inner.mode(path)?;
+        self.inner.set_mode(path, mode)?;
+        self.record(JournalEntry::ModeChanged {
+            path: path.to_string(),
+            mode: previous,
+        });
+        Ok(())
+    }
+
+    fn inspect(&self, path: &str) -> Result<Entry, IoError> {
+        self.inner.inspect(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::BTreeMap, sync::Arc};
+
+    use super::*;
+    use crate::{Linker, MemoryBackend, SilentSink};
+
+    fn seeded() -> MemoryBackend {
+        MemoryBackend::new()
+            .with_file("source")
+            .with_file("file")
+            .with_dir("dir")
+            .with_file("dir/nested")
+    }
+
+    fn entries_of(backend: &JournalBackend<MemoryBackend>) -> BTreeMap<String, Entry> {
+        ["source", "file", "dir", "dir/nested", "new"]
+            .iter()
+            .map(|path| (path.to_string(), backend.inspect(path).unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn it_should_move_removed_entries_aside() {
+        let backend = JournalBackend::wrap(seeded());
+
+        backend.remove("dir").unwrap();
+
+        assert_eq!(backend.inspect("dir").unwrap(), Entry::Missing);
+        match &backend.journal()[..] {
+            [JournalEntry::MovedAside { target, stash }] => {
+                assert_eq!(target, "dir");
+                assert_eq!(backend.inspect(stash).unwrap(), Entry::Dir);
+                assert_eq!(
+                    backend.inspect(&format!("{}/nested", stash)).unwrap(),
+                    Entry::File
+                );
+            }
+            journal => panic!("Unexpected journal: {:?}", journal),
+        }
+    }
+
+    #[test]
+    fn it_should_restore_everything_on_rollback() {
+        let backend = JournalBackend::wrap(seeded());
+        let before = entries_of(&backend);
+
+        backend.remove("file").unwrap();
+        backend.create("source", "file").unwrap();
+        backend.remove("dir").unwrap();
+        backend.create("source", "dir").unwrap();
+        backend.create("source", "new").unwrap();
+        assert!(backend.create("source", "new").is_err());
+        assert!(backend.has_failed());
+
+        backend.rollback().unwrap();
+
+        assert_eq!(entries_of(&backend), before);
+        assert!(backend.journal().is_empty());
+    }
+
+    #[test]
+    fn it_should_remove_written_files_and_their_new_dirs_on_rollback() {
+        let backend = JournalBackend::wrap(seeded());
+
+        backend.write("dir/secret", b"secret", 0o600).unwrap();
+        backend.write("new/deep/secret", b"secret", 0o600).unwrap();
+        assert_eq!(
+            backend.journal()[1],
+            JournalEntry::Written {
+                path: "new/deep/secret".to_string(),
+                new_dirs: vec!["new".to_string(), "new/deep".to_string()],
+            }
+        );
+
+        backend.rollback().unwrap();
+
+        assert_eq!(backend.inspect("dir").unwrap(), Entry::Dir);
+        assert_eq!(backend.inspect("dir/secret").unwrap(), Entry::Missing);
+        assert_eq!(backend.inspect("new").unwrap(), Entry::Missing);
+    }
+
+    #[test]
+    fn it_should_restore_modes_on_rollback() {
+        let backend = JournalBackend::wrap(seeded());
+
+        backend.set_mode("file", 0o600).unwrap();
+        backend.set_mode("file", 0o400).unwrap();
+        assert_eq!(backend.mode("file").unwrap(), 0o400);
+
+        backend.rollback().unwrap();
+
+        assert_eq!(backend.mode("file").unwrap(), 0o644);
+    }
+
+    #[test]
+    fn it_should_keep_entries_which_can_not_be_undone() {
+        let backend = JournalBackend::wrap(seeded());
+
+        backend.remove("file").unwrap();
+        backend.create("source", "new").unwrap();
+        // Someone else removes the link before the rollback
+        backend.inner.remove("new").unwrap();
+
+        assert!(backend.rollback().is_err());
+        assert_eq!(backend.inspect("file").unwrap(), Entry::File);
+        assert_eq!(
+            backend.journal(),
+            vec![JournalEntry::Created {
+                target: "new".to_string()
+            }]
+        );
+    }
+
+    #[test]
+    fn it_should_delete_entries_moved_aside_on_commit() {
+        let backend = JournalBackend::wrap(seeded());
+
+        backend.remove("file").unwrap();
+        let stash = match &backend.journal()[..] {
+            [JournalEntry::MovedAside { stash, .. }] => stash.clone(),
+            journal => panic!("Unexpected journal: {:?}", journal),
+        };
+        backend.commit().unwrap();
+
+        assert_eq!(backend.inspect("file").unwrap(), Entry::Missing);
+        assert_eq!(backend.inspect(&stash).unwrap(), Entry::Missing);
+        assert!(backend.journal().is_empty());
+    }
+
+    #[test]
+    fn it_should_rollback_a_failed_run() {
+        let backend = Arc::new(JournalBackend::wrap(
+            MemoryBackend::new().with_file("a").with_file("taken"),
+        ));
+
+        Linker::new()
+            .with_backend(backend.clone())
+            .with_sink(SilentSink)
+            .link_all(&[
+                ("a".to_string(), "b".to_string()),
+                ("a".to_string(), "taken".to_string()),
+            ]);
+
+        assert!(backend.has_failed());
+        backend.rollback().unwrap();
+        assert_eq!(backend.inspect("b").unwrap(), Entry::Missing);
+        assert_eq!(backend.inspect("taken").unwrap(), Entry::File);
+    }
+}
@@ -0,0 +1,85 @@
+use std::{io::Error as IoError, sync::Arc};
+
+/// What currently exists at a path, as seen by a [`LinkBackend`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Entry {
+    Missing,
+    File,
+    Dir,
+    /// A soft link and the path it points to
+    Symlink(String),
+}
+
+/// The operations the linker needs to apply a set of links
+///
+/// # Example
+///
+/// ```rust
+/// use ezcfg_linker::{Entry, LinkBackend, MemoryBackend};
+///
+/// let backend = MemoryBackend::new().with_file("/dotfiles/.zshrc");
+/// backend.create("/dotfiles/.zshrc", "/home/.zshrc").unwrap();
+///
+/// assert_eq!(
+///     backend.inspect("/home/.zshrc").unwrap(),
+///     Entry::Symlink("/dotfiles/.zshrc".to_string())
+/// );
+/// ```
+pub trait LinkBackend: Send + Sync + 'static {
+    /// Create a soft link at `target` pointing to `source`
+    fn create(&self, source: &str, target: &str) -> Result<(), IoError>;
+
+    /// Remove whatever exists at `target`, directories are removed recursively
+    fn remove(&self, target: &str) -> Result<(), IoError>;
+
+    /// Move whatever exists at `from` to `to`
+    fn rename(&self, from: &str, to: &str) -> Result<(), IoError>;
+
+    /// Write `contents` to a new file at `path` with the permissions `mode`,
+    /// creating its missing parent directories
+    fn write(&self, path: &str, contents: &[u8], mode: u32) -> Result<(), IoError>;
+
+    /// Get the permissions of `path`, following the soft link
+    fn mode(&self, path: &str) -> Result<u32, IoError>;
+
+    /// Set the permissions of `path` to `mode`, following the soft link
+    fn set_mode(&self, path: &str, mode: u32) -> Result<(), IoError>;
+
+    /// Look at `path` without following the soft link
+    fn inspect(&self, path: &str) -> Result<Entry, IoError>;
+
+    /// Whether `target` is a soft link pointing to `source`
+    fn is_linked(&self, source: &str, target: &str) -> bool {
+        matches!(self.inspect(target), Ok(Entry::Symlink(dest)) if dest == source)
+    }
+}
+
+impl<B: LinkBackend> LinkBackend for Arc<B> {
+    fn create(&self, source: &str, target: &str) -> Result<(), IoError> {
+        (**self).create(source, target)
+    }
+
+    fn remove(&self, target: &str) -> Result<(), IoError> {
+        (**self).remove(target)
+    }
+
+    fn rename(&self, from: &str, to: &str) -> Result<(), IoError> {
+        (**self).rename(from, to)
+    }
+
+    fn write(&self, path: &str, contents: &[u8], mode: u32) -> Result<(), IoError> {
+        (**self).write(path, contents, mode)
+    }
+
+    fn mode(&self, path: &str) -> Result<u32, IoError> {
+        (**self).mode(path)
+    }
+
+    fn set_mode(&self, path: &str, mode: u32) -> Result<(), IoError> {
+        (**self).set_mode(path, mode)
+    }
+
+    fn inspect(&self, path: &str) -> Result<Entry, IoError> {
+        (**self).inspect(path)
+    }
+}
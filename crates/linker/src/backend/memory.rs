@@ -0,0 +1,261 @@
+use std::{
+    collections::BTreeMap,
+    io::{Error as IoError, ErrorKind as IoErrorKind},
+    path::Path,
+    sync::Mutex,
+};
+
+use super::{Entry, LinkBackend};
+
+/// An in-memory fake of the filesystem, mainly for tests
+///
+/// # Example
+///
+/// ```rust
+/// use ezcfg_linker::{Entry, Linker, MemoryBackend, SilentSink};
+/// use std::sync::Arc;
+///
+/// let backend = Arc::new(MemoryBackend::new().with_dir("/dotfiles/nvim"));
+/// let links = vec![("/dotfiles/nvim".to_string(), "/home/.config/nvim".to_string())];
+///
+/// Linker::new()
+///     .with_backend(backend.clone())
+///     .with_sink(SilentSink)
+///     .link_all(&links);
+///
+/// assert_eq!(
+///     backend.entries()["/home/.config/nvim"],
+///     Entry::Symlink("/dotfiles/nvim".to_string())
+/// );
+/// ```
+#[derive(Debug, Default)]
+pub struct MemoryBackend {
+    /// Each entry with its permissions
+    entries: Mutex<BTreeMap<String, (Entry, u32)>>,
+}
+
+impl MemoryBackend {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Seed a regular file at `path`, with the mode `0o644`
+    pub fn with_file(self, path: &str) -> Self {
+        self.insert(path, Entry::File, 0o644);
+        self
+    }
+
+    /// Seed a directory at `path`, with the mode `0o755`
+    pub fn with_dir(self, path: &str) -> Self {
+        self.insert(path, Entry::Dir, 0o755);
+        self
+    }
+
+    /// Seed a soft link at `path` pointing to `dest`
+    pub fn with_symlink(self, path: &str, dest: &str) -> Self {
+        self.insert(path, Entry::Symlink(dest.to_string()), 0o777);
+        self
+    }
+
+    /// Get a snapshot of all entries
+    pub fn entries(&self) -> BTreeMap<String, Entry> {
+        self.entries
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(path, (entry, _))| (path.clone(), entry.clone()))
+            .collect()
+    }
+
+    fn insert(&self, path: &str, entry: Entry, mode: u32) {
+        self.entries
+            .lock()
+            .unwrap()
+            .insert(path.to_string(), (entry, mode));
+    }
+}
+
+/// The path of the entry `path` leads to, following soft links as Linux does
+fn follow<'a>(
+    entries: &'a BTreeMap<String, (Entry, u32)>,
+    mut path: &'a str,
+) -> Result<&'a str, IoError> {
+    for _ in 0..=40 {
+        match entries.get(path) {
+            Some((Entry::Symlink(dest), _)) => path = dest,
+            Some(_) => return Ok(path),
+            None => {
+                return Err(IoError::new(
+                    IoErrorKind::NotFound,
+                    format!("No such file or directory: {}", path),
+                ))
+            }
+        }
+    }
+    Err(IoError::other(format!(
+        "Too many levels of soft links: {}",
+        path
+    )))
+}
+
+impl LinkBackend for MemoryBackend {
+    fn create(&self, source: &str, target: &str) -> Result<(), IoError> {
+        let mut entries = self.entries.lock().unwrap();
+        if entries.contains_key(target) {
+            return Err(IoError::new(
+                IoErrorKind::AlreadyExists,
+                format!("File exists: {}", target),
+            ));
+        }
+
+        entries.insert(
+            target.to_string(),
+            (Entry::Symlink(source.to_string()), 0o777),
+        );
+        Ok(())
+    }
+
+    fn remove(&self, target: &str) -> Result<(), IoError> {
+        let mut entries = self.entries.lock().unwrap();
+        if entries.remove(target).is_none() {
+            return Err(IoError::new(
+                IoErrorKind::NotFound,
+                format!("No such file or directory: {}", target),
+            ));
+        }
+
+        let prefix = format!("{}/", target.trim_end_matches('/'));
+        entries.retain(|path, _| !path.starts_with(&prefix));
+        Ok(())
+    }
+
+    fn rename(&self, from: &str, to: &str) -> Result<(), IoError> {
+        let mut entries = self.entries.lock().unwrap();
+        let entry = match entries.remove(from) {
+            Some(entry) => entry,
+            None => {
+                return Err(IoError::new(
+                    IoErrorKind::NotFound,
+                    format!("No such file or directory: {}", from),
+                ))
+            }
+        };
+
+        let prefix = format!("{}/", from.trim_end_matches('/'));
+        let children = entries
+            .keys()
+            .filter(|path| path.starts_with(&prefix))
+            .cloned()
+            .collect::<Vec<_>>();
+        for child in children {
+            let child_entry = entries.remove(&child).unwrap();
+            let moved = format!("{}/{}", to, child.strip_prefix(&prefix).unwrap());
+            entries.insert(moved, child_entry);
+        }
+
+        entries.insert(to.to_string(), entry);
+        Ok(())
+    }
+
+    fn write(&self, path: &str, _contents: &[u8], mode: u32) -> Result<(), IoError> {
+        let mut entries = self.entries.lock().unwrap();
+        if entries.contains_key(path) {
+            return Err(IoError::new(
+                IoErrorKind::AlreadyExists,
+                format!("File exists: {}", path),
+            ));
+        }
+
+        for parent in Path::new(path).ancestors().skip(1) {
+            let parent = parent.to_str().unwrap();
+            if !parent.is_empty() && parent != "/" {
+                entries
+                    .entry(parent.to_string())
+                    .or_insert((Entry::Dir, 0o755));
+            }
+        }
+        entries.insert(path.to_string(), (Entry::File, mode));
+        Ok(())
+    }
+
+    fn mode(&self, path: &str) -> Result<u32, IoError> {
+        let entries = self.entries.lock().unwrap();
+        let path = follow(&entries, path)?;
+        Ok(entries[path].1)
+    }
+
+    fn set_mode(&self, path: &str, mode: u32) -> Result<(), IoError> {
+        let mut entries = self.entries.lock().unwrap();
+        let path = follow(&entries, path)?.to_string();
+        entries.get_mut(&path).unwrap().1 = mode;
+        Ok(())
+    }
+
+    fn inspect(&self, path: &str) -> Result<Entry, IoError> {
+        let entries = self.entries.lock().unwrap();
+        Ok(entries
+            .get(path)
+            .map(|(entry, _)| entry.clone())
+            .unwrap_or(Entry::Missing))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_should_refuse_to_create_over_existing_entry() {
+        let backend = MemoryBackend::new().with_file("a");
+
+        let err = backend.create("b", "a").unwrap_err();
+        assert_eq!(err.kind(), IoErrorKind::AlreadyExists);
+        assert_eq!(backend.inspect("a").unwrap(), Entry::File);
+    }
+
+    #[test]
+    fn it_should_remove_dir_recursively() {
+        let backend = MemoryBackend::new()
+            .with_dir("a")
+            .with_file("a/b")
+            .with_file("ab");
+
+        backend.remove("a").unwrap();
+
+        assert_eq!(backend.inspect("a").unwrap(), Entry::Missing);
+        assert_eq!(backend.inspect("a/b").unwrap(), Entry::Missing);
+        assert_eq!(backend.inspect("ab").unwrap(), Entry::File);
+        assert_eq!(
+            backend.remove("a").unwrap_err().kind(),
+            IoErrorKind::NotFound
+        );
+    }
+
+    #[test]
+    fn it_should_set_mode_through_soft_link() {
+        let backend = MemoryBackend::new()
+            .with_file("a")
+            .with_symlink("b", "a")
+            .with_symlink("c", "missing");
+
+        backend.set_mode("b", 0o600).unwrap();
+
+        assert_eq!(backend.mode("a").unwrap(), 0o600);
+        assert_eq!(
+            backend.set_mode("c", 0o600).unwrap_err().kind(),
+            IoErrorKind::NotFound
+        );
+    }
+
+    #[test]
+    fn it_should_rename_dir_with_children() {
+        let backend = MemoryBackend::new().with_dir("a").with_file("a/b");
+
+        backend.rename("a", "c").unwrap();
+
+        assert_eq!(backend.inspect("a").unwrap(), Entry::Missing);
+        assert_eq!(backend.inspect("a/b").unwrap(), Entry::Missing);
+        assert_eq!(backend.inspect("c").unwrap(), Entry::Dir);
+        assert_eq!(backend.inspect("c/b").unwrap(), Entry::File);
+    }
+}
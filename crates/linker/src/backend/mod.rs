@@ -0,0 +1,16 @@
+//! This mod abstracts the filesystem operations performed by the linker.
+//!
+//! The linker only talks to a [`LinkBackend`], so the same linking logic
+//! can run against the real filesystem, an in-memory fake or a recorder.
+
+mod fs;
+mod journal;
+mod link_backend;
+mod memory;
+mod recording;
+
+pub use fs::FsBackend;
+pub use journal::{JournalBackend, JournalEntry};
+pub use link_backend::{Entry, LinkBackend};
+pub use memory::MemoryBackend;
+pub use recording::{Operation, RecordingBackend};
@@ -0,0 +1,142 @@
+use std::{io::Error as IoError, sync::Mutex};
+
+use super::{Entry, FsBackend, LinkBackend};
+
+/// A modifying operation captured by [`RecordingBackend`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Operation {
+    Create { source: String, target: String },
+    Remove { target: String },
+    Rename { from: String, to: String },
+    Write { path: String },
+    SetMode { path: String, mode: u32 },
+}
+
+/// A backend for dry runs
+///
+/// It records every `create`, `remove`, `rename`, `write` and `set_mode` instead of applying them,
+/// while `mode` and `inspect` are still answered by the inner backend.
+///
+/// # Example
+///
+/// ```rust
+/// use ezcfg_linker::{LinkBackend, Operation, RecordingBackend};
+///
+/// let backend = RecordingBackend::new();
+/// backend.create("/dotfiles/.zshrc", "/home/.zshrc").unwrap();
+///
+/// assert_eq!(
+///     backend.operations(),
+///     vec![Operation::Create {
+///         source: "/dotfiles/.zshrc".to_string(),
+///         target: "/home/.zshrc".to_string(),
+///     }]
+/// );
+/// ```
+#[derive(Debug, Default)]
+pub struct RecordingBackend<B = FsBackend> {
+    inner: B,
+    operations: Mutex<Vec<Operation>>,
+}
+
+impl RecordingBackend {
+    /// Record operations on top of the real filesystem
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<B: LinkBackend> RecordingBackend<B> {
+    /// Record operations on top of another backend
+    pub fn wrap(inner: B) -> Self {
+        RecordingBackend {
+            inner,
+            operations: Mutex::new(vec![]),
+        }
+    }
+
+    /// Get all recorded operations in order
+    pub fn operations(&self) -> Vec<Operation> {
+        self.operations.lock().unwrap().clone()
+    }
+
+    fn record(&self, operation: Operation) {
+        self.operations.lock().unwrap().push(operation);
+    }
+}
+
+impl<B: LinkBackend> LinkBackend for RecordingBackend<B> {
+    fn create(&self, source: &str, target: &str) -> Result<(), IoError> {
+        self.record(Operation::Create {
+            source: source.to_string(),
+            target: target.to_string(),
+        });
+        Ok(())
+    }
+
+    fn remove(&self, target: &str) -> Result<(), IoError> {
+        self.record(Operation::Remove {
+            target: target.to_string(),
+        });
+        Ok(())
+    }
+
+    fn rename(&self, from: &str, to: &str) -> Result<(), IoError> {
+        self.record(Operation::Rename {
+            from: from.to_string(),
+            to: to.to_string(),
+        });
+        Ok(())
+    }
+
+    fn write(&self, path: &str, _contents: &[u8], _mode: u32) -> Result<(), IoError> {
+        self.record(Operation::Write {
+            path: path.to_string(),
+        });
+        Ok(())
+    }
+
+    fn mode(&self, path: &str) -> Result<u32, IoError> {
+        self.inner.mode(path)
+    }
+
+    fn set_mode(&self, path: &str, mode: u32) -> Result<(), IoError> {
+        self.record(Operation::SetMode {
+            path: path.to_string(),
+            mode,
+        });
+        Ok(())
+    }
+
+    fn inspect(&self, path: &str) -> Result<Entry, IoError> {
+        self.inner.inspect(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MemoryBackend;
+
+    #[test]
+    fn it_should_record_without_touching_inner_backend() {
+        let backend = RecordingBackend::wrap(MemoryBackend::new().with_file("a"));
+
+        backend.remove("a").unwrap();
+        backend.create("b", "a").unwrap();
+
+        assert_eq!(backend.inspect("a").unwrap(), Entry::File);
+        assert_eq!(
+            backend.operations(),
+            vec![
+                Operation::Remove {
+                    target: "a".to_string()
+                },
+                Operation::Create {
+                    source: "b".to_string(),
+                    target: "a".to_string()
+                },
+            ]
+        );
+    }
+}
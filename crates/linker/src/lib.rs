@@ -1,6 +1,17 @@
+mod backend;
 mod linker;
 mod pool;
-mod spinner;
+mod report;
+mod schedule;
+mod sink;
 
-pub use linker::{link_all, link_all_with_filter};
-pub use pool::ThreadPool;
+pub use backend::{
+    Entry, FsBackend, JournalBackend, JournalEntry, LinkBackend, MemoryBackend, Operation,
+    RecordingBackend,
+};
+pub use linker::{link_all, link_all_with_filter, link_all_with_hooks, Linker};
+pub use pool::{JobPanic, PoolStats, Scope, TaskHandle, ThreadPool};
+pub use report::{Decision, LinkReport, SkipReason};
+pub use sink::{
+    LinkEventSink, LinkTask, LinkTiming, SilentSink, SpinnerSink, TimedHandle, TimingSink,
+};
@@ -1,95 +1,871 @@
-use std::env::consts::OS;
-use std::io::{Error as IoError, ErrorKind as IoErrorKind};
-use std::os::unix::fs::symlink as unix_symlink;
+use std::{
+    io::Error as IoError,
+    panic::{catch_unwind, AssertUnwindSafe},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
 
-use crate::spinner::{pb_setup, pb_spinning, pb_task_fail, pb_task_success, pb_waiting};
-use crate::ThreadPool;
+use crate::{
+    pool::panic_message, schedule::schedule, Decision, FsBackend, LinkBackend, LinkEventSink,
+    LinkReport, LinkTask, PoolStats, SkipReason, SpinnerSink, ThreadPool,
+};
 
-/// Create all soft links according to a [`Links`]
+/// Create soft links through a pluggable [`LinkBackend`],
+/// reporting the progress to a [`LinkEventSink`]
 ///
-/// Additionally, it allows passing in a series of closure functions
-// pub fn link_all_with_hooks<FTaskSend, ResBeforeSend, FOnSetup, ResOnSetup, FOnSuccess, FOnFail>(
-//     links: &Vec<(String, String)>,
-//     before_task_send: FTaskSend,
-//     on_task_setup: FOnSetup,
-//     on_task_success: FOnSuccess,
-//     on_task_fail: FOnFail,
-// ) where
-//     ResBeforeSend: Send + 'static,
-//     FTaskSend: Fn(&str, &str, usize) -> ResBeforeSend,
-//     FOnSetup: Fn(&str, &str, usize, ResBeforeSend) -> ResOnSetup + Copy + Send + 'static,
-//     FOnSuccess: Fn(&str, &str, usize, ResOnSetup) + Copy + Send + 'static,
-//     FOnFail: Fn(&str, &str, usize, &IoError, ResOnSetup) + Copy + Send + 'static,
-// {
-//     let pool = ThreadPool::global();
-
-//     links
-//         .iter()
-//         .enumerate()
-//         .filter(|(_, (source, target))| check_path(source, target).is_some())
-//         .for_each(|(idx, (source, target))| {
-//             let source = source.to_string();
-//             let target = target.to_string();
-//             let res_on_task_setup = before_task_send(&source, &target, idx);
-
-//             pool.execute(move || {
-//                 let res_before_task = on_task_setup(&source, &target, idx, res_on_task_setup);
-//                 match soft_link(&source, &target) {
-//                     Ok(_) => on_task_success(&source, &target, idx, res_before_task),
-//                     Err(e) => on_task_fail(&source, &target, idx, &e, res_before_task),
-//                 }
-//             });
-//         });
-
-//     pool.join();
-// }
-pub fn link_all_with_filter<F>(links: &Vec<(String, String)>, filter: F)
-where
-    F: Fn(&str, &str, usize, usize) -> bool,
-{
-    let pool = ThreadPool::global();
-
-    let len = links.len();
-    links
-        .iter()
-        .enumerate()
-        .filter(|(idx, (source, target))| filter(source, target, *idx, len))
-        .for_each(|(idx, (source, target))| {
-            let source = source.to_string();
-            let target = target.to_string();
-
-            let pb = pb_setup();
-            pb_waiting(&pb, &source, &target, idx, len);
-
-            pool.execute(move || {
-                pb_spinning(&pb, &source, &target, idx, len);
-                match soft_link(&source, &target) {
-                    Ok(_) => pb_task_success(&pb, &source, &target, idx, len),
-                    Err(e) => pb_task_fail(&pb, &source, &target, idx, len, &e),
+/// By default it operates on the real filesystem and renders spinners.
+///
+/// # Example
+///
+/// ```rust
+/// use ezcfg_linker::{Linker, Operation, RecordingBackend, SilentSink};
+/// use std::sync::Arc;
+///
+/// let backend = Arc::new(RecordingBackend::new());
+/// let links = vec![("/dotfiles/.zshrc".to_string(), "/home/.zshrc".to_string())];
+///
+/// Linker::new()
+///     .with_backend(backend.clone())
+///     .with_sink(SilentSink)
+///     .link_all(&links);
+///
+/// assert_eq!(backend.operations().len(), 1);
+/// ```
+pub struct Linker<B = FsBackend, S = SpinnerSink> {
+    backend: Arc<B>,
+    sink: Arc<S>,
+    workers: Workers,
+    /// Set to stop linking, see [`Linker::with_cancel`]
+    cancel: Option<Arc<AtomicBool>>,
+}
+
+/// Where the links are created, see [`Linker::with_jobs`]
+enum Workers {
+    /// The global pool, with one thread per CPU
+    Global,
+    /// The calling thread, one link after the other
+    Caller,
+    Pool(ThreadPool),
+}
+
+/// What became of a task given to [`Linker::run`]
+enum Outcome<O, R> {
+    Done(O),
+    Panicked,
+    /// The run has been cancelled before the task started, its value is given back
+    Cancelled(R),
+}
+
+impl Linker {
+    pub fn new() -> Self {
+        Linker {
+            backend: Arc::new(FsBackend),
+            sink: Arc::new(SpinnerSink),
+            workers: Workers::Global,
+            cancel: None,
+        }
+    }
+}
+
+impl Default for Linker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<B: LinkBackend, S: LinkEventSink> Linker<B, S> {
+    /// Replace the backend performing the filesystem operations
+    pub fn with_backend<T: LinkBackend>(self, backend: T) -> Linker<T, S> {
+        Linker {
+            backend: Arc::new(backend),
+            sink: self.sink,
+            workers: self.workers,
+            cancel: self.cancel,
+        }
+    }
+
+    /// Replace the sink receiving the progress events
+    pub fn with_sink<T: LinkEventSink>(self, sink: T) -> Linker<B, T> {
+        Linker {
+            backend: self.backend,
+            sink: Arc::new(sink),
+            workers: self.workers,
+            cancel: self.cancel,
+        }
+    }
+
+    /// Create at most `jobs` links at the same time, instead of one per CPU
+    ///
+    /// With a single job, the links are created one by one on the calling thread,
+    /// in the order they are given in, parent targets first. `0` keeps the default.
+    /// Otherwise the threads are started right away, and kept until the linker is dropped.
+    pub fn with_jobs(self, jobs: usize) -> Self {
+        let workers = match jobs {
+            0 => Workers::Global,
+            1 => Workers::Caller,
+            jobs => Workers::Pool(ThreadPool::new(jobs)),
+        };
+        Linker { workers, ..self }
+    }
+
+    /// Get how many links the pool of this linker has created, and how long they waited and ran
+    ///
+    /// Nothing is returned with a single job. The global pool, used by default,
+    /// also counts the tasks of other linkers. The pool is joined first,
+    /// since a thread records the times of a task right after the task is reported done.
+    pub fn pool_stats(&self) -> Option<PoolStats> {
+        self.pool().map(|pool| {
+            pool.join();
+            pool.stats()
+        })
+    }
+
+    fn pool(&self) -> Option<&ThreadPool> {
+        match &self.workers {
+            Workers::Global => Some(ThreadPool::global()),
+            Workers::Caller => None,
+            Workers::Pool(pool) => Some(pool),
+        }
+    }
+
+    /// Stop linking once `cancel` is set, from a signal handler for example
+    ///
+    /// The links in progress are finished, while the remaining ones are reported
+    /// as cancelled without being filtered nor linked.
+    pub fn with_cancel(self, cancel: Arc<AtomicBool>) -> Self {
+        Linker {
+            cancel: Some(cancel),
+            ..self
+        }
+    }
+
+    /// Create all soft links accepted by `filter`
+    ///
+    /// The links which are filtered out are reported to the sink with the reason,
+    /// and the returned report counts the links in each state.
+    pub fn link_all_with_filter<F, D>(&self, links: &[(String, String)], filter: F) -> LinkReport
+    where
+        F: Fn(&str, &str, usize, usize) -> D,
+        D: Into<Decision>,
+    {
+        let sink = &self.sink;
+
+        self.link_all_with_hooks(
+            links,
+            filter,
+            |task| sink.on_waiting(task),
+            |task, handle| {
+                sink.on_linking(task, &handle);
+                handle
+            },
+            |task, handle| sink.on_success(task, handle),
+            |task, err, handle| sink.on_fail(task, handle, err),
+            |task, reason| sink.on_skipped(task, reason),
+            |task, handle| sink.on_cancelled(task, handle),
+        )
+    }
+
+    /// Create all soft links accepted by `filter`,
+    /// calling the hooks instead of the sink of this linker
+    ///
+    /// - `before_task_send` is called on the calling thread once every link has been filtered,
+    ///   before the task is dispatched; the target of a [`Decision::Replace`] is only removed
+    ///   once the task starts
+    /// - `on_task_setup` is called on a worker thread right before linking
+    /// - `on_task_success` or `on_task_fail` is called with the linking result
+    /// - `on_task_skipped` is called on the calling thread instead, for the links filtered out
+    ///   and the ones left once the run is cancelled
+    /// - `on_task_cancelled` is called once the run is over, for the tasks dispatched
+    ///   but cancelled before they started
+    ///
+    /// Independent links are linked concurrently, while a link whose target or source
+    /// lies inside the target of another one waits for it, so `~/.config` is always
+    /// linked before `~/.config/nvim`.
+    ///
+    /// The value returned by a hook is passed to the next one,
+    /// and the returned report counts the links in each state.
+    /// A link whose hooks panic is counted as failed.
+    #[allow(clippy::too_many_arguments)]
+    pub fn link_all_with_hooks<
+        FFilter,
+        ResFilter,
+        FTaskSend,
+        ResBeforeSend,
+        FOnSetup,
+        ResOnSetup,
+        FOnSuccess,
+        FOnFail,
+        FOnSkipped,
+        FOnCancelled,
+    >(
+        &self,
+        links: &[(String, String)],
+        filter: FFilter,
+        before_task_send: FTaskSend,
+        on_task_setup: FOnSetup,
+        on_task_success: FOnSuccess,
+        on_task_fail: FOnFail,
+        on_task_skipped: FOnSkipped,
+        on_task_cancelled: FOnCancelled,
+    ) -> LinkReport
+    where
+        FFilter: Fn(&str, &str, usize, usize) -> ResFilter,
+        ResFilter: Into<Decision>,
+        ResBeforeSend: Send,
+        FTaskSend: Fn(&LinkTask) -> ResBeforeSend,
+        FOnSetup: Fn(&LinkTask, ResBeforeSend) -> ResOnSetup + Sync,
+        FOnSuccess: Fn(&LinkTask, ResOnSetup) + Sync,
+        FOnFail: Fn(&LinkTask, &IoError, ResOnSetup) + Sync,
+        FOnSkipped: Fn(&LinkTask, &SkipReason),
+        FOnCancelled: Fn(&LinkTask, ResBeforeSend),
+    {
+        let mut report = LinkReport::default();
+
+        let len = links.len();
+        let mut accepted = vec![];
+        for (idx, (source, target)) in links.iter().enumerate() {
+            let task = LinkTask {
+                source: source.to_string(),
+                target: target.to_string(),
+                idx,
+                len,
+            };
+            if self.is_cancelled() {
+                on_task_skipped(&task, &SkipReason::Cancelled);
+                report.count_skip(&SkipReason::Cancelled);
+                continue;
+            }
+
+            match filter(source, target, idx, len).into() {
+                Decision::Link => accepted.push((task, false)),
+                Decision::Replace => accepted.push((task, true)),
+                Decision::Skip(reason) => {
+                    on_task_skipped(&task, &reason);
+                    report.count_skip(&reason);
                 }
-            });
+            }
+        }
+
+        // Every link is filtered before any is dispatched,
+        // so the time spent filtering is not counted as waiting
+        let tasks = accepted
+            .into_iter()
+            .map(|(task, replace)| {
+                let res_before_send = before_task_send(&task);
+                (task, (replace, res_before_send))
+            })
+            .collect();
+
+        let outcomes = self.run(tasks, |task, (replace, res_before_send)| {
+            let res_on_setup = on_task_setup(task, res_before_send);
+            match self.create(task, replace) {
+                Ok(_) => {
+                    on_task_success(task, res_on_setup);
+                    true
+                }
+                Err(e) => {
+                    on_task_fail(task, &e, res_on_setup);
+                    false
+                }
+            }
         });
+        for (task, outcome) in outcomes {
+            match outcome {
+                Outcome::Done(true) => report.linked += 1,
+                Outcome::Done(false) | Outcome::Panicked => report.failed += 1,
+                Outcome::Cancelled((_, res_before_send)) => {
+                    on_task_cancelled(&task, res_before_send);
+                    report.cancelled += 1;
+                }
+            }
+        }
+
+        report
+    }
+
+    /// Call `job` with each of `tasks` and the value attached to it, and collect the outcomes
+    ///
+    /// The tasks are run wave after wave as given by [`schedule`], on the calling thread
+    /// with a single job. A panic of `job` is caught and returned as the outcome of its task,
+    /// and the tasks which have not started when the run is cancelled give their value back.
+    fn run<R, O, J>(&self, tasks: Vec<(LinkTask, R)>, job: J) -> Vec<(LinkTask, Outcome<O, R>)>
+    where
+        R: Send,
+        O: Send,
+        J: Fn(&LinkTask, R) -> O + Sync,
+    {
+        let paths = tasks
+            .iter()
+            .map(|(task, _)| (task.source.as_str(), task.target.as_str()))
+            .collect::<Vec<_>>();
+        let waves = schedule(&paths);
+
+        let (tasks, mut values): (Vec<_>, Vec<_>) = tasks
+            .into_iter()
+            .map(|(task, value)| (task, Some(value)))
+            .unzip();
+        let mut outcomes = tasks.iter().map(|_| None).collect::<Vec<_>>();
+
+        match self.pool() {
+            None => {
+                for pos in waves.into_iter().flatten() {
+                    let value = values[pos].take().unwrap();
+                    let task = &tasks[pos];
+                    outcomes[pos] = Some(match self.is_cancelled() {
+                        true => Outcome::Cancelled(value),
+                        false => match catch_unwind(AssertUnwindSafe(|| job(task, value))) {
+                            Ok(res) => Outcome::Done(res),
+                            Err(_) => Outcome::Panicked,
+                        },
+                    });
+                }
+            }
+            Some(pool) => pool.scope(|scope| {
+                // Only the links of a wave are independent, so each wave waits for the previous one
+                for wave in waves {
+                    let handles = wave
+                        .into_iter()
+                        .map(|pos| {
+                            let value = values[pos].take().unwrap();
+                            let (task, job) = (&tasks[pos], &job);
+                            let handle = scope.spawn(move || match self.is_cancelled() {
+                                true => Err(value),
+                                false => Ok(job(task, value)),
+                            });
+                            (pos, handle)
+                        })
+                        .collect::<Vec<_>>();
+                    for (pos, handle) in handles {
+                        outcomes[pos] = Some(match handle.join() {
+                            Ok(Ok(res)) => Outcome::Done(res),
+                            Ok(Err(value)) => Outcome::Cancelled(value),
+                            Err(_) => Outcome::Panicked,
+                        });
+                    }
+                }
+            }),
+        }
+
+        tasks
+            .into_iter()
+            .zip(outcomes)
+            .map(|(task, outcome)| (task, outcome.unwrap()))
+            .collect()
+    }
 
-    pool.join();
+    /// Create the soft link of `task`, removing its target first when it is replaced
+    ///
+    /// A panic of the backend is turned into an error, so the task is reported
+    /// as failed along with the panic message instead of being left in progress.
+    fn create(&self, task: &LinkTask, replace: bool) -> Result<(), IoError> {
+        catch_unwind(AssertUnwindSafe(|| {
+            if replace {
+                self.backend.remove(&task.target)?;
+            }
+            self.backend.create(&task.source, &task.target)
+        }))
+        .unwrap_or_else(|payload| {
+            let msg = format!("the backend panicked: {}", panic_message(payload.as_ref()));
+            Err(IoError::other(msg))
+        })
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.cancel
+            .as_ref()
+            .is_some_and(|cancel| cancel.load(Ordering::SeqCst))
+    }
+
+    pub fn link_all(&self, links: &[(String, String)]) -> LinkReport {
+        self.link_all_with_filter(links, |_source, _target, _idx, _len| true)
+    }
+}
+
+pub fn link_all_with_filter<F, D>(links: &[(String, String)], filter: F) -> LinkReport
+where
+    F: Fn(&str, &str, usize, usize) -> D,
+    D: Into<Decision>,
+{
+    Linker::new().link_all_with_filter(links, filter)
+}
+
+pub fn link_all(links: &[(String, String)]) -> LinkReport {
+    Linker::new().link_all(links)
 }
 
-pub fn link_all(links: &Vec<(String, String)>) {
-    link_all_with_filter(links, |_source, _target, _idx, _len| true)
+/// Create all soft links accepted by `filter`,
+/// reporting the progress through a series of closure functions
+///
+/// See [`Linker::link_all_with_hooks`] for when each hook is called.
+///
+/// # Example
+///
+/// ```rust
+/// use ezcfg_linker::link_all_with_hooks;
+/// use std::time::Instant;
+///
+/// let report = link_all_with_hooks(
+///     &vec![],
+///     |_source, _target, _idx, _len| true,
+///     |_task| Instant::now(),
+///     |_task, queued_at| queued_at,
+///     |task, queued_at| println!("{} linked in {:?}", task.target, queued_at.elapsed()),
+///     |task, err, _queued_at| eprintln!("{} failed: {}", task.target, err),
+///     |task, reason| println!("{} skipped: {}", task.target, reason),
+///     |task, _queued_at| println!("{} cancelled", task.target),
+/// );
+/// assert_eq!(report.total(), 0);
+/// ```
+#[allow(clippy::too_many_arguments)]
+pub fn link_all_with_hooks<
+    FFilter,
+    ResFilter,
+    FTaskSend,
+    ResBeforeSend,
+    FOnSetup,
+    ResOnSetup,
+    FOnSuccess,
+    FOnFail,
+    FOnSkipped,
+    FOnCancelled,
+>(
+    links: &[(String, String)],
+    filter: FFilter,
+    before_task_send: FTaskSend,
+    on_task_setup: FOnSetup,
+    on_task_success: FOnSuccess,
+    on_task_fail: FOnFail,
+    on_task_skipped: FOnSkipped,
+    on_task_cancelled: FOnCancelled,
+) -> LinkReport
+where
+    FFilter: Fn(&str, &str, usize, usize) -> ResFilter,
+    ResFilter: Into<Decision>,
+    ResBeforeSend: Send,
+    FTaskSend: Fn(&LinkTask) -> ResBeforeSend,
+    FOnSetup: Fn(&LinkTask, ResBeforeSend) -> ResOnSetup + Sync,
+    FOnSuccess: Fn(&LinkTask, ResOnSetup) + Sync,
+    FOnFail: Fn(&LinkTask, &IoError, ResOnSetup) + Sync,
+    FOnSkipped: Fn(&LinkTask, &SkipReason),
+    FOnCancelled: Fn(&LinkTask, ResBeforeSend),
+{
+    Linker::new().link_all_with_hooks(
+        links,
+        filter,
+        before_task_send,
+        on_task_setup,
+        on_task_success,
+        on_task_fail,
+        on_task_skipped,
+        on_task_cancelled,
+    )
 }
 
-fn soft_link(source: &str, target: &str) -> Result<(), IoError> {
-    if cfg!(target_family = "unix") {
-        unix_symlink(source, target)?;
-        return Ok(());
-    } else if cfg!(target_family = "windows") {
-        return Err(IoError::new(
-            IoErrorKind::Other,
-            format!("Unsupported OS: {}", OS),
-        ));
-    }
-
-    Err(IoError::new(
-        IoErrorKind::Other,
-        format!("Unsupported OS: {}", OS),
-    ))
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{Arc, Mutex},
+        thread,
+    };
+
+    use super::*;
+    use crate::{Entry, MemoryBackend, Operation, RecordingBackend, SilentSink, SkipReason};
+
+    fn links(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(source, target)| (source.to_string(), target.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn it_should_link_through_memory_backend() {
+        let backend = Arc::new(MemoryBackend::new().with_file("a").with_file("c"));
+
+        Linker::new()
+            .with_backend(backend.clone())
+            .with_sink(SilentSink)
+            .link_all(&links(&[("a", "b"), ("c", "d")]));
+
+        assert_eq!(backend.inspect("b").unwrap(), Entry::Symlink("a".into()));
+        assert_eq!(backend.inspect("d").unwrap(), Entry::Symlink("c".into()));
+    }
+
+    #[test]
+    fn it_should_skip_filtered_links() {
+        let backend = Arc::new(RecordingBackend::wrap(MemoryBackend::new()));
+
+        Linker::new()
+            .with_backend(backend.clone())
+            .with_sink(SilentSink)
+            .link_all_with_filter(&links(&[("a", "b"), ("c", "d")]), |source, _, _, _| {
+                source == "c"
+            });
+
+        assert_eq!(
+            backend.operations(),
+            vec![Operation::Create {
+                source: "c".to_string(),
+                target: "d".to_string()
+            }]
+        );
+    }
+
+    #[test]
+    fn it_should_link_parent_targets_first() {
+        for _ in 0..20 {
+            let backend = Arc::new(RecordingBackend::wrap(MemoryBackend::new()));
+
+            Linker::new()
+                .with_backend(backend.clone())
+                .with_sink(SilentSink)
+                .link_all(&links(&[
+                    ("init.lua", "/home/.config/nvim/init.lua"),
+                    ("nvim", "/home/.config/nvim"),
+                    ("config", "/home/.config"),
+                ]));
+
+            let targets = backend
+                .operations()
+                .into_iter()
+                .map(|op| match op {
+                    Operation::Create { target, .. } => target,
+                    op => panic!("unexpected operation: {:?}", op),
+                })
+                .collect::<Vec<_>>();
+            assert_eq!(
+                targets,
+                vec![
+                    "/home/.config",
+                    "/home/.config/nvim",
+                    "/home/.config/nvim/init.lua"
+                ]
+            );
+        }
+    }
+
+    #[test]
+    fn it_should_link_in_order_on_calling_thread_with_one_job() {
+        let caller = thread::current().id();
+        let events = Arc::new(Mutex::new(vec![]));
+        let setup_events = events.clone();
+
+        Linker::new()
+            .with_backend(MemoryBackend::new())
+            .with_jobs(1)
+            .link_all_with_hooks(
+                &links(&[("a", "/home/b"), ("c", "/home/d"), ("e", "/home/f")]),
+                |_, _, _, _| true,
+                |_| (),
+                move |task, _| {
+                    assert_eq!(thread::current().id(), caller);
+                    setup_events.lock().unwrap().push(task.idx);
+                },
+                |_, _| {},
+                |_, _, _| {},
+                |_, _| {},
+                |_, _| {},
+            );
+
+        assert_eq!(*events.lock().unwrap(), vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn it_should_link_with_own_pool() {
+        let backend = Arc::new(MemoryBackend::new().with_file("a").with_file("c"));
+
+        let linker = Linker::new()
+            .with_backend(backend.clone())
+            .with_sink(SilentSink)
+            .with_jobs(2);
+        let report = linker.link_all(&links(&[("a", "b"), ("c", "d")]));
+
+        assert_eq!(report.linked, 2);
+        assert_eq!(backend.inspect("d").unwrap(), Entry::Symlink("c".into()));
+        assert_eq!(linker.pool_stats().unwrap().completed, 2);
+        assert_eq!(Linker::new().with_jobs(1).pool_stats(), None);
+    }
+
+    #[test]
+    fn it_should_pass_hook_results_along() {
+        let backend = MemoryBackend::new().with_file("a").with_file("taken");
+        let events = Arc::new(Mutex::new(vec![]));
+        let (success_events, fail_events) = (events.clone(), events.clone());
+
+        Linker::new().with_backend(backend).link_all_with_hooks(
+            &links(&[("a", "b"), ("a", "taken")]),
+            |_, _, _, _| true,
+            |task| task.idx * 10,
+            |_, res_before_send| res_before_send + 1,
+            move |task, res_on_setup| {
+                success_events
+                    .lock()
+                    .unwrap()
+                    .push(format!("ok {} {}", task.target, res_on_setup))
+            },
+            move |task, _err, res_on_setup| {
+                fail_events
+                    .lock()
+                    .unwrap()
+                    .push(format!("fail {} {}", task.target, res_on_setup))
+            },
+            |_, _| {},
+            |_, _| {},
+        );
+
+        let mut events = events.lock().unwrap().clone();
+        events.sort();
+        assert_eq!(events, vec!["fail taken 11", "ok b 1"]);
+    }
+
+    #[test]
+    fn it_should_report_skipped_and_cancelled_links_to_hooks() {
+        let cancel = Arc::new(AtomicBool::new(false));
+        let events = Mutex::new(vec![]);
+
+        let report = Linker::new()
+            .with_backend(MemoryBackend::new())
+            .with_jobs(1)
+            .with_cancel(cancel.clone())
+            .link_all_with_hooks(
+                &links(&[("a", "b"), ("c", "d"), ("e", "f")]),
+                |_, target, _, _| target != "d",
+                |task| task.idx,
+                |_, idx| {
+                    cancel.store(true, Ordering::SeqCst);
+                    idx
+                },
+                |_, _| {},
+                |_, _, _| {},
+                |task, reason| {
+                    events
+                        .lock()
+                        .unwrap()
+                        .push(format!("{} {}", task.target, reason))
+                },
+                |task, idx| {
+                    events
+                        .lock()
+                        .unwrap()
+                        .push(format!("{} {}", task.target, idx))
+                },
+            );
+
+        assert_eq!((report.linked, report.skipped, report.cancelled), (1, 1, 1));
+        assert_eq!(
+            *events.lock().unwrap(),
+            vec!["d filtered out".to_string(), "f 2".to_string()]
+        );
+    }
+
+    #[test]
+    fn it_should_keep_replaced_targets_of_cancelled_links() {
+        let cancel = Arc::new(AtomicBool::new(false));
+        let backend = Arc::new(MemoryBackend::new().with_file("b").with_file("d"));
+
+        let report = Linker::new()
+            .with_backend(backend.clone())
+            .with_jobs(1)
+            .with_cancel(cancel.clone())
+            .link_all_with_hooks(
+                &links(&[("a", "b"), ("c", "d")]),
+                |_, _, _, _| Decision::Replace,
+                |_| (),
+                |_, _| cancel.store(true, Ordering::SeqCst),
+                |_, _| {},
+                |_, _, _| {},
+                |_, _| {},
+                |_, _| {},
+            );
+
+        assert_eq!((report.linked, report.cancelled), (1, 1));
+        assert_eq!(backend.inspect("b").unwrap(), Entry::Symlink("a".into()));
+        assert_eq!(backend.inspect("d").unwrap(), Entry::File);
+    }
+
+    #[test]
+    fn it_should_count_panicking_links_as_failed() {
+        struct PanicOn(&'static str);
+
+        impl LinkEventSink for PanicOn {
+            type Handle = ();
+
+            fn on_waiting(&self, _task: &LinkTask) {}
+            fn on_linking(&self, task: &LinkTask, _handle: &()) {
+                if task.target == self.0 {
+                    panic!("boom");
+                }
+            }
+            fn on_success(&self, _task: &LinkTask, _handle: ()) {}
+            fn on_fail(&self, _task: &LinkTask, _handle: (), _err: &IoError) {}
+        }
+
+        for linker in [
+            Linker::new().with_backend(MemoryBackend::new()),
+            Linker::new()
+                .with_backend(MemoryBackend::new())
+                .with_jobs(1),
+        ] {
+            let report = linker.with_sink(PanicOn("d")).link_all(&links(&[
+                ("a", "b"),
+                ("c", "d"),
+                ("e", "f"),
+            ]));
+
+            assert_eq!((report.linked, report.failed), (2, 1));
+        }
+    }
+
+    #[test]
+    fn it_should_report_backend_panics_as_failures() {
+        struct PanicOn(&'static str, MemoryBackend);
+
+        impl LinkBackend for PanicOn {
+            fn create(&self, source: &str, target: &str) -> Result<(), IoError> {
+                if target == self.0 {
+                    panic!("boom");
+                }
+                self.1.create(source, target)
+            }
+            fn remove(&self, target: &str) -> Result<(), IoError> {
+                self.1.remove(target)
+            }
+            fn rename(&self, from: &str, to: &str) -> Result<(), IoError> {
+                self.1.rename(from, to)
+            }
+            fn write(&self, path: &str, contents: &[u8], mode: u32) -> Result<(), IoError> {
+                self.1.write(path, contents, mode)
+            }
+            fn mode(&self, path: &str) -> Result<u32, IoError> {
+                self.1.mode(path)
+            }
+            fn set_mode(&self, path: &str, mode: u32) -> Result<(), IoError> {
+                self.1.set_mode(path, mode)
+            }
+            fn inspect(&self, path: &str) -> Result<Entry, IoError> {
+                self.1.inspect(path)
+            }
+        }
+
+        struct FailRecorder(Mutex<Vec<String>>);
+
+        impl LinkEventSink for FailRecorder {
+            type Handle = ();
+
+            fn on_waiting(&self, _task: &LinkTask) {}
+            fn on_linking(&self, _task: &LinkTask, _handle: &()) {}
+            fn on_success(&self, _task: &LinkTask, _handle: ()) {}
+            fn on_fail(&self, task: &LinkTask, _handle: (), err: &IoError) {
+                self.0
+                    .lock()
+                    .unwrap()
+                    .push(format!("{}: {}", task.target, err));
+            }
+        }
+
+        for jobs in [0, 1] {
+            let sink = Arc::new(FailRecorder(Mutex::new(vec![])));
+            let report = Linker::new()
+                .with_backend(PanicOn("d", MemoryBackend::new()))
+                .with_sink(sink.clone())
+                .with_jobs(jobs)
+                .link_all(&links(&[("a", "b"), ("c", "d"), ("e", "f")]));
+
+            assert_eq!((report.linked, report.failed), (2, 1));
+            assert_eq!(
+                *sink.0.lock().unwrap(),
+                vec!["d: the backend panicked: boom"]
+            );
+        }
+    }
+
+    #[test]
+    fn it_should_stop_linking_once_cancelled() {
+        struct CancelOn(&'static str, Arc<AtomicBool>);
+
+        impl LinkEventSink for CancelOn {
+            type Handle = ();
+
+            fn on_waiting(&self, _task: &LinkTask) {}
+            fn on_linking(&self, task: &LinkTask, _handle: &()) {
+                if task.target == self.0 {
+                    self.1.store(true, Ordering::SeqCst);
+                }
+            }
+            fn on_success(&self, _task: &LinkTask, _handle: ()) {}
+            fn on_fail(&self, _task: &LinkTask, _handle: (), _err: &IoError) {}
+        }
+
+        let cancel = Arc::new(AtomicBool::new(false));
+        let backend = Arc::new(RecordingBackend::wrap(MemoryBackend::new()));
+        let report = Linker::new()
+            .with_backend(backend.clone())
+            .with_sink(CancelOn("b", cancel.clone()))
+            .with_jobs(1)
+            .with_cancel(cancel.clone())
+            .link_all(&links(&[("a", "b"), ("c", "d"), ("e", "f")]));
+
+        // The link in progress is finished
+        assert_eq!((report.linked, report.cancelled), (1, 2));
+        assert_eq!(backend.operations().len(), 1);
+
+        let report = Linker::new()
+            .with_backend(MemoryBackend::new())
+            .with_sink(SilentSink)
+            .with_cancel(cancel)
+            .link_all(&links(&[("a", "b"), ("c", "d")]));
+        assert_eq!(report.cancelled, 2);
+        assert_eq!(report.total(), 2);
+    }
+
+    #[test]
+    fn it_should_report_every_link() {
+        struct SkipRecorder(Mutex<Vec<(usize, SkipReason)>>);
+
+        impl LinkEventSink for SkipRecorder {
+            type Handle = ();
+
+            fn on_waiting(&self, _task: &LinkTask) {}
+            fn on_linking(&self, _task: &LinkTask, _handle: &()) {}
+            fn on_success(&self, _task: &LinkTask, _handle: ()) {}
+            fn on_fail(&self, _task: &LinkTask, _handle: (), _err: &IoError) {}
+            fn on_skipped(&self, task: &LinkTask, reason: &SkipReason) {
+                self.0.lock().unwrap().push((task.idx, reason.clone()));
+            }
+        }
+
+        let sink = Arc::new(SkipRecorder(Mutex::new(vec![])));
+        let report = Linker::new()
+            .with_backend(MemoryBackend::new().with_file("taken"))
+            .with_sink(sink.clone())
+            .link_all_with_filter(
+                &links(&[("a", "b"), ("a", "taken"), ("a", "c"), ("a", "d")]),
+                |_, target, _, _| match target {
+                    "c" => Decision::Skip(SkipReason::UpToDate),
+                    "d" => Decision::skip("nope"),
+                    _ => Decision::Link,
+                },
+            );
+
+        assert_eq!(
+            report,
+            LinkReport {
+                linked: 1,
+                up_to_date: 1,
+                skipped: 1,
+                failed: 1,
+                cancelled: 0,
+            }
+        );
+        assert_eq!(report.total(), 4);
+        assert_eq!(
+            *sink.0.lock().unwrap(),
+            vec![
+                (2, SkipReason::UpToDate),
+                (3, SkipReason::Rejected("nope".to_string()))
+            ]
+        );
+    }
 }
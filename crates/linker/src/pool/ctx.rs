@@ -1,10 +1,17 @@
-use std::sync::{
-    atomic::{AtomicUsize, Ordering},
-    mpsc::Receiver,
-    Condvar, Mutex,
+use std::{
+    any::Any,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc::Receiver,
+        Condvar, Mutex,
+    },
 };
 
-use super::thread_pool::Action;
+use super::{
+    panic::{panic_message, JobPanic},
+    stats::JobTimes,
+    thread_pool::Action,
+};
 
 pub struct ThreadPoolContext {
     pub receiver: Mutex<Receiver<Action>>,
@@ -12,6 +19,8 @@ pub struct ThreadPoolContext {
     pub actived_count: AtomicUsize,
     pub pending_count: AtomicUsize,
     pub packing_count: AtomicUsize,
+    pub panics: Mutex<Vec<JobPanic>>,
+    pub times: Mutex<JobTimes>,
 
     pub cond_lock: Mutex<()>,
     pub cond: Condvar,
@@ -24,6 +33,14 @@ impl ThreadPoolContext {
         actived_count > 0 || pendding_count > 0
     }
 
+    /// Keep track of a task which panicked with `payload`
+    pub fn record_panic(&self, payload: &(dyn Any + Send)) {
+        self.packing_count.fetch_add(1, Ordering::SeqCst);
+        self.panics.lock().unwrap().push(JobPanic {
+            message: panic_message(payload),
+        });
+    }
+
     pub fn no_work_notify_all(&self) {
         // Lock the current thread
         // then notify the waiting threads
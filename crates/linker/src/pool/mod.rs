@@ -4,7 +4,15 @@
 //! I don't need most of the other functions, so I simplified its implementation.
 
 mod ctx;
-mod sentinel;
+mod panic;
+mod scope;
+mod stats;
+mod task_handle;
 mod thread_pool;
 
+pub(crate) use panic::panic_message;
+pub use panic::JobPanic;
+pub use scope::Scope;
+pub use stats::PoolStats;
+pub use task_handle::TaskHandle;
 pub use thread_pool::ThreadPool;
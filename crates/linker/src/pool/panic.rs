@@ -0,0 +1,19 @@
+use std::any::Any;
+
+/// A panic caught while running a task of a [`ThreadPool`](super::ThreadPool)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JobPanic {
+    /// The message the task panicked with
+    pub message: String,
+}
+
+/// The message of a panic `payload`, when it is a string as with `panic!`
+pub(crate) fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "Box<dyn Any>".to_string()
+    }
+}
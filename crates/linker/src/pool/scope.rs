@@ -0,0 +1,195 @@
+use std::{
+    marker::PhantomData,
+    mem::transmute,
+    panic::{catch_unwind, resume_unwind, AssertUnwindSafe},
+    sync::{mpsc::channel, Arc, Condvar, Mutex},
+};
+
+use super::{task_handle::TaskHandle, thread_pool::ThreadPool};
+
+/// A scope to spawn tasks borrowing from the environment in, see [`ThreadPool::scope`]
+pub struct Scope<'scope, 'env: 'scope> {
+    pool: &'scope ThreadPool,
+    pending: Arc<Pending>,
+    scope: PhantomData<&'scope mut &'scope ()>,
+    env: PhantomData<&'env mut &'env ()>,
+}
+
+/// How many tasks of a scope have not finished yet
+#[derive(Default)]
+struct Pending {
+    count: Mutex<usize>,
+    cond: Condvar,
+}
+
+/// Counts a task as finished when dropped, whether it ran or not
+struct PendingGuard(Arc<Pending>);
+
+impl Drop for PendingGuard {
+    fn drop(&mut self) {
+        let mut count = self.0.count.lock().unwrap();
+        *count -= 1;
+        self.0.cond.notify_all();
+    }
+}
+
+/// A task along with its guard, the task is dropped first as fields drop in order
+struct ScopedJob<F> {
+    job: F,
+    _guard: PendingGuard,
+}
+
+impl<'scope> Scope<'scope, '_> {
+    /// Run `job` in the pool, it may borrow anything which outlives the scope
+    ///
+    /// Panics of the task are caught and returned by [`TaskHandle::join`].
+    pub fn spawn<F, T>(&'scope self, job: F) -> TaskHandle<T>
+    where
+        F: FnOnce() -> T + Send + 'scope,
+        T: Send + 'scope,
+    {
+        *self.pending.count.lock().unwrap() += 1;
+        let scoped = ScopedJob {
+            job,
+            _guard: PendingGuard(self.pending.clone()),
+        };
+
+        let (sender, receiver) = channel();
+        let ctx = self.pool.ctx.clone();
+        let job: Box<dyn FnOnce() + Send + 'scope> = Box::new(move || {
+            let ScopedJob { job, _guard } = scoped;
+            let res = catch_unwind(AssertUnwindSafe(job));
+            if let Err(payload) = &res {
+                ctx.record_panic(payload.as_ref());
+            }
+            let _ = sender.send(res);
+        });
+        // SAFETY: `ThreadPool::scope` does not return before the guard of every task has
+        // been dropped, which happens once the task ran or has been dropped itself,
+        // so nothing the task borrows is used after the end of the scope.
+        let job: Box<dyn FnOnce() + Send + 'static> = unsafe { transmute(job) };
+        self.pool.execute(job);
+
+        TaskHandle::new(receiver)
+    }
+
+    fn wait(&self) {
+        let mut count = self.pending.count.lock().unwrap();
+        while *count > 0 {
+            count = self.pending.cond.wait(count).unwrap();
+        }
+    }
+}
+
+impl ThreadPool {
+    /// Run `f` with a scope to spawn tasks which borrow from the environment in
+    ///
+    /// Like [`std::thread::scope`], every task spawned in the scope has finished when it returns.
+    /// Unlike [`ThreadPool::join`], only the tasks of the scope are waited for.
+    ///
+    /// # Note
+    ///
+    /// Calling it from a task of the same pool may deadlock,
+    /// when every thread of the pool ends up waiting for a scope.
+    ///
+    /// # Example
+    /// ```rust
+    /// use ezcfg_linker::ThreadPool;
+    ///
+    /// let pool = ThreadPool::new(2);
+    /// let words = vec!["hello", "world"];
+    ///
+    /// let lens = pool.scope(|scope| {
+    ///     let handles = words
+    ///         .iter()
+    ///         .map(|word| scope.spawn(move || word.len()))
+    ///         .collect::<Vec<_>>();
+    ///     handles
+    ///         .into_iter()
+    ///         .map(|handle| handle.join().unwrap())
+    ///         .collect::<Vec<_>>()
+    /// });
+    ///
+    /// assert_eq!(lens, vec![5, 5]);
+    /// ```
+    pub fn scope<'env, F, R>(&self, f: F) -> R
+    where
+        F: for<'scope> FnOnce(&'scope Scope<'scope, 'env>) -> R,
+    {
+        let scope = Scope {
+            pool: self,
+            pending: Arc::default(),
+            scope: PhantomData,
+            env: PhantomData,
+        };
+
+        let res = catch_unwind(AssertUnwindSafe(|| f(&scope)));
+        scope.wait();
+        match res {
+            Ok(res) => res,
+            Err(payload) => resume_unwind(payload),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::atomic::{AtomicUsize, Ordering},
+        thread::sleep,
+        time::Duration,
+    };
+
+    use super::*;
+
+    #[test]
+    fn it_should_borrow_from_the_environment() {
+        let pool = ThreadPool::new(4);
+        let counter = AtomicUsize::new(0);
+
+        pool.scope(|scope| {
+            for _ in 0..50 {
+                scope.spawn(|| {
+                    sleep(Duration::from_millis(1));
+                    counter.fetch_add(1, Ordering::SeqCst);
+                });
+            }
+        });
+
+        assert_eq!(counter.load(Ordering::SeqCst), 50);
+    }
+
+    #[test]
+    fn it_should_return_results_and_panics() {
+        let pool = ThreadPool::new(2);
+
+        let (ok, panicked) = pool.scope(|scope| {
+            let ok = scope.spawn(|| 21 * 2);
+            let panicked = scope.spawn(|| -> usize { panic!("boom") });
+            (ok.join(), panicked.join())
+        });
+
+        assert_eq!(ok.unwrap(), 42);
+        assert_eq!(*panicked.unwrap_err().downcast::<&str>().unwrap(), "boom");
+        assert_eq!(pool.panics()[0].message, "boom");
+    }
+
+    #[test]
+    fn it_should_wait_for_tasks_when_the_scope_panics() {
+        let pool = ThreadPool::new(2);
+        let counter = AtomicUsize::new(0);
+
+        let res = catch_unwind(AssertUnwindSafe(|| {
+            pool.scope(|scope| {
+                scope.spawn(|| {
+                    sleep(Duration::from_millis(100));
+                    counter.fetch_add(1, Ordering::SeqCst);
+                });
+                panic!("boom");
+            })
+        }));
+
+        assert!(res.is_err());
+        assert_eq!(counter.load(Ordering::SeqCst), 1);
+    }
+}
@@ -0,0 +1,53 @@
+use std::time::Duration;
+
+/// A snapshot of what a [`ThreadPool`](super::ThreadPool) has been doing
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct PoolStats {
+    /// How many tasks have finished, panicking or not
+    pub completed: usize,
+    /// The longest time a task waited in the queue before a thread picked it
+    pub max_wait: Duration,
+    pub avg_wait: Duration,
+    /// The longest time a task took to run
+    pub max_run: Duration,
+    pub avg_run: Duration,
+    /// How many threads have been respawned after dying, which is always 0:
+    /// tasks run under `catch_unwind`, so a panicking task never takes its thread down
+    pub respawned: usize,
+}
+
+/// Running totals of the times of the finished tasks
+#[derive(Debug, Default)]
+pub struct JobTimes {
+    completed: usize,
+    total_wait: Duration,
+    max_wait: Duration,
+    total_run: Duration,
+    max_run: Duration,
+}
+
+impl JobTimes {
+    pub fn record(&mut self, wait: Duration, run: Duration) {
+        self.completed += 1;
+        self.total_wait += wait;
+        self.max_wait = self.max_wait.max(wait);
+        self.total_run += run;
+        self.max_run = self.max_run.max(run);
+    }
+
+    pub fn stats(&self) -> PoolStats {
+        let avg = |total: Duration| match self.completed {
+            0 => Duration::ZERO,
+            completed => Duration::from_nanos((total.as_nanos() / completed as u128) as u64),
+        };
+
+        PoolStats {
+            completed: self.completed,
+            max_wait: self.max_wait,
+            avg_wait: avg(self.total_wait),
+            max_run: self.max_run,
+            avg_run: avg(self.total_run),
+            respawned: 0,
+        }
+    }
+}
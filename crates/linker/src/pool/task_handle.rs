@@ -0,0 +1,35 @@
+use std::{
+    fmt::{self, Debug, Formatter},
+    sync::mpsc::Receiver,
+    thread::Result as ThreadResult,
+};
+
+/// Owned permission to wait for a task submitted to a [`ThreadPool`](super::ThreadPool)
+///
+/// Dropping the handle does not cancel the task, its result is just discarded.
+pub struct TaskHandle<T> {
+    receiver: Receiver<ThreadResult<T>>,
+}
+
+impl<T> TaskHandle<T> {
+    pub(crate) fn new(receiver: Receiver<ThreadResult<T>>) -> Self {
+        TaskHandle { receiver }
+    }
+
+    /// Wait for the task to finish
+    ///
+    /// Like [`std::thread::JoinHandle::join`], the error is the payload the task panicked with.
+    /// A task which has been dropped before running, when the pool shut down,
+    /// is reported as a panic too.
+    pub fn join(self) -> ThreadResult<T> {
+        self.receiver
+            .recv()
+            .unwrap_or_else(|_| Err(Box::new("the task has been dropped before running")))
+    }
+}
+
+impl<T> Debug for TaskHandle<T> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.debug_struct("TaskHandle").finish_non_exhaustive()
+    }
+}
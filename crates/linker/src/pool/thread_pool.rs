@@ -1,21 +1,29 @@
 use std::{
     fmt::{self, Debug, Formatter},
+    panic::{catch_unwind, AssertUnwindSafe},
     sync::{
         atomic::{AtomicUsize, Ordering},
         mpsc::{channel, Sender},
         Arc, Condvar, Mutex, OnceLock,
     },
     thread::spawn,
+    time::Instant,
 };
 
-use super::{ctx::ThreadPoolContext, sentinel::Sentinel};
+use super::{ctx::ThreadPoolContext, panic::JobPanic, stats::PoolStats, task_handle::TaskHandle};
 
 pub enum Message<T> {
     NewJob(T),
     Shutdown,
 }
 
-pub type Action = Message<Box<dyn FnOnce() + Send + 'static>>;
+/// A task, along with when it has been queued
+pub struct Job {
+    queued_at: Instant,
+    run: Box<dyn FnOnce() + Send + 'static>,
+}
+
+pub type Action = Message<Job>;
 
 /// Global single instance
 static POOL: OnceLock<ThreadPool> = OnceLock::<ThreadPool>::new();
@@ -28,6 +36,9 @@ static POOL: OnceLock<ThreadPool> = OnceLock::<ThreadPool>::new();
 ///
 /// But this will not happen under the current design (with global singulation).
 ///
+/// To borrow from the environment, or to wait for some tasks only,
+/// use [`ThreadPool::scope`] or [`ThreadPool::submit`] instead of `join`.
+///
 /// # Example
 ///
 /// ```rust
@@ -46,7 +57,7 @@ static POOL: OnceLock<ThreadPool> = OnceLock::<ThreadPool>::new();
 /// ```
 pub struct ThreadPool {
     sender: Sender<Action>,
-    ctx: Arc<ThreadPoolContext>,
+    pub(super) ctx: Arc<ThreadPoolContext>,
 }
 
 impl ThreadPool {
@@ -67,9 +78,22 @@ impl ThreadPool {
     /// Create a new thread pool with the specified size.
     /// The size must be greater than 0.
     ///
+    /// The threads exit once the pool and all of its clones are dropped.
+    ///
     /// # Panic
     /// If the size is less than or equal to 0, it will panic.
-    fn new(size: usize) -> Self {
+    ///
+    /// # Example
+    /// ```rust
+    /// use ezcfg_linker::ThreadPool;
+    ///
+    /// let pool = ThreadPool::new(2);
+    /// pool.execute(|| {
+    ///   println!("Hello, world!");
+    /// });
+    /// pool.join();
+    /// ```
+    pub fn new(size: usize) -> Self {
         assert!(size > 0, "ThreadPool size must be greater than 0");
 
         let (sender, receiver) = channel();
@@ -80,6 +104,8 @@ impl ThreadPool {
             actived_count: AtomicUsize::new(0),
             pending_count: AtomicUsize::new(0),
             packing_count: AtomicUsize::new(0),
+            panics: Mutex::new(vec![]),
+            times: Mutex::default(),
 
             cond_lock: Mutex::new(()),
             cond: Condvar::new(),
@@ -126,7 +152,7 @@ impl ThreadPool {
         self.ctx.pending_count.load(Ordering::Relaxed)
     }
 
-    /// Get the number of panicing threads in the thread pool
+    /// Get the number of tasks which panicked in the thread pool
     ///
     /// # Examples
     ///
@@ -150,6 +176,48 @@ impl ThreadPool {
         self.ctx.packing_count.load(Ordering::Relaxed)
     }
 
+    /// Get the panics caught so far, in the order they happened
+    ///
+    /// A panicking task does not take its thread down, the other tasks keep running.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ezcfg_linker::ThreadPool;
+    ///
+    /// let pool = ThreadPool::new(2);
+    /// pool.execute(|| panic!("boom"));
+    /// pool.join();
+    ///
+    /// assert_eq!(pool.panics()[0].message, "boom");
+    /// ```
+    pub fn panics(&self) -> Vec<JobPanic> {
+        self.ctx.panics.lock().unwrap().clone()
+    }
+
+    /// Get how many tasks have finished, and how long they waited and ran
+    ///
+    /// Threads survive the panics of their tasks, so none is ever respawned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ezcfg_linker::ThreadPool;
+    ///
+    /// let pool = ThreadPool::new(2);
+    /// for _ in 0..4 {
+    ///     pool.execute(|| println!("Hello, world!"));
+    /// }
+    /// pool.join();
+    ///
+    /// let stats = pool.stats();
+    /// assert_eq!(stats.completed, 4);
+    /// assert!(stats.avg_run <= stats.max_run);
+    /// ```
+    pub fn stats(&self) -> PoolStats {
+        self.ctx.times.lock().unwrap().stats()
+    }
+
     /// Execute a task in the thread pool
     /// The task will be executed in parallel
     ///
@@ -167,7 +235,42 @@ impl ThreadPool {
         F: FnOnce() + Send + 'static,
     {
         self.ctx.pending_count.fetch_add(1, Ordering::SeqCst);
-        self.sender.send(Message::NewJob(Box::new(job))).unwrap();
+        let job = Job {
+            queued_at: Instant::now(),
+            run: Box::new(job),
+        };
+        self.sender.send(Message::NewJob(job)).unwrap();
+    }
+
+    /// Execute a task in the thread pool, and get a handle to wait for its result
+    ///
+    /// Panics of the task are returned by [`TaskHandle::join`],
+    /// and are kept track of by [`ThreadPool::panics`] as well.
+    ///
+    /// # Example
+    /// ```rust
+    /// use ezcfg_linker::ThreadPool;
+    ///
+    /// let pool = ThreadPool::global();
+    /// let handle = pool.submit(|| 21 * 2);
+    ///
+    /// assert_eq!(handle.join().unwrap(), 42);
+    /// ```
+    pub fn submit<F, T>(&self, job: F) -> TaskHandle<T>
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        let (sender, receiver) = channel();
+        let ctx = self.ctx.clone();
+        self.execute(move || {
+            let res = catch_unwind(AssertUnwindSafe(job));
+            if let Err(payload) = &res {
+                ctx.record_panic(payload.as_ref());
+            }
+            let _ = sender.send(res);
+        });
+        TaskHandle::new(receiver)
     }
 
     /// Shutdown the thread pool
@@ -216,11 +319,8 @@ impl ThreadPool {
     }
 }
 
-pub fn spawn_in_pool(ctx: Arc<ThreadPoolContext>) {
+fn spawn_in_pool(ctx: Arc<ThreadPoolContext>) {
     spawn(move || {
-        // Create a sentinel to monitor the status of the thread
-        let mut sentinel = Sentinel::new(&ctx);
-
         loop {
             // Release the mutex lock as soon as possible
             let msg = {
@@ -239,18 +339,18 @@ pub fn spawn_in_pool(ctx: Arc<ThreadPoolContext>) {
             ctx.actived_count.fetch_add(1, Ordering::SeqCst);
             ctx.pending_count.fetch_sub(1, Ordering::SeqCst);
 
-            job();
+            let started_at = Instant::now();
+            if let Err(payload) = catch_unwind(AssertUnwindSafe(job.run)) {
+                ctx.record_panic(payload.as_ref());
+            }
+            ctx.times
+                .lock()
+                .unwrap()
+                .record(started_at - job.queued_at, started_at.elapsed());
 
             ctx.actived_count.fetch_sub(1, Ordering::SeqCst);
             ctx.no_work_notify_all();
         }
-
-        // Only when the internal code is executed correctly can it get here.
-        // That means the thread is not in a panic state.
-        //
-        // If the thread is in a panic state, the sentinel will be dropped
-        // and it will be spawned again.
-        sentinel.done();
     });
 }
 
@@ -355,6 +455,28 @@ mod tests {
         assert_eq!(receiver.iter().take(task_count).sum::<usize>(), task_count);
     }
 
+    // -----------------------------------------
+    // ThreadPool::submit                      -
+    // -----------------------------------------
+
+    #[test]
+    fn it_should_return_results_of_submitted_tasks() {
+        let pool = ThreadPool::new(4);
+
+        let handles = (0..10)
+            .map(|n| pool.submit(move || n * 2))
+            .collect::<Vec<_>>();
+        let panicked = pool.submit(|| panic!("boom"));
+
+        let results = handles
+            .into_iter()
+            .map(|handle| handle.join().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(results, (0..10).map(|n| n * 2).collect::<Vec<_>>());
+        assert!(panicked.join().is_err());
+        assert_eq!(pool.panicing_count(), 1);
+    }
+
     // -----------------------------------------
     // ThreadPool::join                        -
     // -----------------------------------------
@@ -384,7 +506,7 @@ mod tests {
     }
 
     // -----------------------------------------
-    // Sentinel                                -
+    // Panics                                  -
     // -----------------------------------------
 
     #[test]
@@ -397,6 +519,15 @@ mod tests {
         pool.join();
 
         assert_eq!(pool.panicing_count(), 8);
+        assert_eq!(
+            pool.panics(),
+            vec![
+                JobPanic {
+                    message: "".to_string()
+                };
+                8
+            ]
+        );
 
         let (tx, rx) = channel();
         for _ in 0..8 {
@@ -406,7 +537,7 @@ mod tests {
             });
         }
 
-        assert_eq!(rx.iter().take(8).fold(0, |acc, num| acc + num), 8);
+        assert_eq!(rx.iter().take(8).sum::<i32>(), 8);
     }
 
     // -----------------------------------------
@@ -450,7 +581,7 @@ mod tests {
                     });
                 }
                 drop(tx);
-                rx.iter().fold(0, |acc, num| acc + num)
+                rx.iter().sum::<i32>()
             })
         };
         let t2 = {
@@ -467,7 +598,7 @@ mod tests {
                     });
                 }
                 drop(tx);
-                rx.iter().fold(1, |acc, num| acc * num)
+                rx.iter().product::<i32>()
             })
         };
 
@@ -475,6 +606,29 @@ mod tests {
         assert_eq!(5040, t2.join().unwrap());
     }
 
+    // -----------------------------------------
+    // ThreadPool::stats                       -
+    // -----------------------------------------
+
+    #[test]
+    fn it_should_measure_jobs() {
+        let pool = ThreadPool::new(1);
+        assert_eq!(pool.stats(), PoolStats::default());
+
+        for _ in 0..4 {
+            pool.execute(|| sleep(Duration::from_millis(10)));
+        }
+        pool.join();
+
+        let stats = pool.stats();
+        assert_eq!(stats.completed, 4);
+        assert!(stats.max_run >= Duration::from_millis(10));
+        assert!(stats.avg_run >= Duration::from_millis(10));
+        // The last job waits for the three others
+        assert!(stats.max_wait >= Duration::from_millis(30));
+        assert_eq!(stats.respawned, 0);
+    }
+
     #[test]
     fn it_should_implement_send() {
         fn assert_send<T: Send>() {}
@@ -0,0 +1,125 @@
+use std::{
+    fmt::{self, Display, Formatter},
+    ops::AddAssign,
+};
+
+/// What a filter decided to do with a link
+///
+/// A `bool` converts into a decision, so plain predicates still work as filters.
+///
+/// # Example
+///
+/// ```rust
+/// use ezcfg_linker::{Decision, LinkReport, Linker, MemoryBackend, SilentSink};
+///
+/// let links = vec![
+///     ("/dotfiles/.zshrc".to_string(), "/home/.zshrc".to_string()),
+///     ("/dotfiles/.vimrc".to_string(), "/home/.vimrc".to_string()),
+/// ];
+///
+/// let report = Linker::new()
+///     .with_backend(MemoryBackend::new())
+///     .with_sink(SilentSink)
+///     .link_all_with_filter(&links, |_source, target, _idx, _len| match target {
+///         "/home/.vimrc" => Decision::skip("not using vim anymore"),
+///         _ => Decision::Link,
+///     });
+///
+/// assert_eq!(report.linked, 1);
+/// assert_eq!(report.skipped, 1);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Decision {
+    Link,
+    /// Remove the existing target, then link
+    ///
+    /// The target is removed by the task creating the link, so it is left
+    /// in place when the run is cancelled before the task starts.
+    Replace,
+    Skip(SkipReason),
+}
+
+impl Decision {
+    /// Skip the link for a human-readable `reason`
+    pub fn skip<S: Into<String>>(reason: S) -> Self {
+        Decision::Skip(SkipReason::Rejected(reason.into()))
+    }
+}
+
+impl From<bool> for Decision {
+    fn from(accepted: bool) -> Self {
+        match accepted {
+            true => Decision::Link,
+            false => Decision::skip("filtered out"),
+        }
+    }
+}
+
+/// Why a link has not been created
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SkipReason {
+    /// The target already points to the source
+    UpToDate,
+    Rejected(String),
+    /// The run has been cancelled before the link was processed
+    Cancelled,
+}
+
+impl Display for SkipReason {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            SkipReason::UpToDate => write!(f, "up to date"),
+            SkipReason::Rejected(reason) => write!(f, "{}", reason),
+            SkipReason::Cancelled => write!(f, "cancelled"),
+        }
+    }
+}
+
+/// How many links ended up in each state after a run
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct LinkReport {
+    pub linked: usize,
+    pub up_to_date: usize,
+    pub skipped: usize,
+    pub failed: usize,
+    /// Links which have not been processed because the run has been cancelled
+    pub cancelled: usize,
+}
+
+impl LinkReport {
+    pub fn total(&self) -> usize {
+        self.linked + self.up_to_date + self.skipped + self.failed + self.cancelled
+    }
+
+    pub(crate) fn count_skip(&mut self, reason: &SkipReason) {
+        match reason {
+            SkipReason::UpToDate => self.up_to_date += 1,
+            SkipReason::Rejected(_) => self.skipped += 1,
+            SkipReason::Cancelled => self.cancelled += 1,
+        }
+    }
+}
+
+impl AddAssign for LinkReport {
+    fn add_assign(&mut self, other: Self) {
+        self.linked += other.linked;
+        self.up_to_date += other.up_to_date;
+        self.skipped += other.skipped;
+        self.failed += other.failed;
+        self.cancelled += other.cancelled;
+    }
+}
+
+impl Display for LinkReport {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} linked, {} up to date, {} skipped, {} failed",
+            self.linked, self.up_to_date, self.skipped, self.failed
+        )?;
+        if self.cancelled > 0 {
+            write!(f, ", {} cancelled", self.cancelled)?;
+        }
+        Ok(())
+    }
+}
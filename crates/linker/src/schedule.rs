@@ -0,0 +1,161 @@
+use std::{collections::HashMap, path::Path};
+
+/// Group links into waves, each of which only depends on the waves before it
+///
+/// A link depends on the links whose target contains its target or its source,
+/// so `~/.config` is linked before `~/.config/nvim` whatever their order in the list.
+/// Links sharing a target keep their order in the list. Links depending on each other
+/// are linked from the first of them in the list, before the links depending on them.
+///
+/// The links of a wave are independent of each other and can be linked concurrently,
+/// a flat set of links makes a single wave.
+pub(crate) fn schedule(links: &[(&str, &str)]) -> Vec<Vec<usize>> {
+    let mut by_target = HashMap::<&Path, Vec<usize>>::new();
+    for (idx, (_, target)) in links.iter().enumerate() {
+        by_target.entry(Path::new(target)).or_default().push(idx);
+    }
+
+    // The links each link waits for, the links waiting for it, and how many are left to wait for
+    let mut dependencies = vec![vec![]; links.len()];
+    let mut dependents = vec![vec![]; links.len()];
+    let mut waiting = vec![0; links.len()];
+    for (idx, (source, target)) in links.iter().enumerate() {
+        let target = Path::new(target);
+        let mut deps = Path::new(source)
+            .ancestors()
+            .chain(target.ancestors().skip(1))
+            .filter_map(|ancestor| by_target.get(ancestor))
+            .flatten()
+            .copied()
+            .filter(|dep| *dep != idx)
+            .collect::<Vec<_>>();
+        deps.extend(by_target[target].iter().take_while(|dep| **dep < idx));
+        deps.sort_unstable();
+        deps.dedup();
+
+        waiting[idx] = deps.len();
+        for dep in deps.iter() {
+            dependents[*dep].push(idx);
+        }
+        dependencies[idx] = deps;
+    }
+
+    let mut done = vec![false; links.len()];
+    let mut waves = vec![];
+    let mut wave = (0..links.len())
+        .filter(|idx| waiting[*idx] == 0)
+        .collect::<Vec<_>>();
+    let mut remaining = links.len();
+    while remaining > 0 {
+        if wave.is_empty() {
+            // The remaining links all wait for each other, or for a cycle
+            wave.push(first_in_cycle(&dependencies, &done));
+        }
+
+        let mut next = vec![];
+        for idx in wave.iter() {
+            done[*idx] = true;
+            for dependent in dependents[*idx].iter() {
+                waiting[*dependent] -= 1;
+                if waiting[*dependent] == 0 && !done[*dependent] {
+                    next.push(*dependent);
+                }
+            }
+        }
+        next.sort_unstable();
+        remaining -= wave.len();
+        waves.push(wave);
+        wave = next;
+    }
+
+    waves
+}
+
+/// The first link in list order of a cycle among the links which are not done
+///
+/// Each of them waits for another one, so following what they wait for
+/// from any of them ends up going round a cycle.
+fn first_in_cycle(dependencies: &[Vec<usize>], done: &[bool]) -> usize {
+    let mut visited = vec![false; done.len()];
+    let mut path = vec![];
+    let mut idx = done.iter().position(|done| !done).unwrap();
+    while !visited[idx] {
+        visited[idx] = true;
+        path.push(idx);
+        idx = *dependencies[idx].iter().find(|dep| !done[**dep]).unwrap();
+    }
+
+    let start = path.iter().position(|visited| *visited == idx).unwrap();
+    *path[start..].iter().min().unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_should_run_flat_links_in_one_wave() {
+        let links = [
+            ("/dotfiles/.zshrc", "/home/.zshrc"),
+            ("/dotfiles/nvim", "/home/.config/nvim"),
+            ("/dotfiles/alacritty", "/home/.config/alacritty"),
+        ];
+
+        assert_eq!(schedule(&links), vec![vec![0, 1, 2]]);
+    }
+
+    #[test]
+    fn it_should_link_parent_targets_first() {
+        let links = [
+            ("/dotfiles/init.lua", "/home/.config/nvim/init.lua"),
+            ("/dotfiles/.zshrc", "/home/.zshrc"),
+            ("/dotfiles/nvim", "/home/.config/nvim"),
+            ("/dotfiles/config", "/home/.config"),
+        ];
+
+        assert_eq!(schedule(&links), vec![vec![1, 3], vec![2], vec![0]]);
+    }
+
+    #[test]
+    fn it_should_link_sources_inside_targets_after_them() {
+        let links = [
+            ("/home/.config/nvim/lua", "/home/.lua"),
+            ("/dotfiles/nvim", "/home/.config/nvim"),
+        ];
+
+        assert_eq!(schedule(&links), vec![vec![1], vec![0]]);
+    }
+
+    #[test]
+    fn it_should_keep_the_order_of_shared_targets() {
+        let links = [
+            ("/dotfiles/a", "/home/.zshrc"),
+            ("/dotfiles/b", "/home/.zshrc"),
+            ("/dotfiles/c", "/home/.zshrc"),
+        ];
+
+        assert_eq!(schedule(&links), vec![vec![0], vec![1], vec![2]]);
+    }
+
+    #[test]
+    fn it_should_break_cycles_in_list_order() {
+        let links = [
+            ("/home/b/x", "/home/a"),
+            ("/home/a/x", "/home/b"),
+            ("/dotfiles/c", "/home/c"),
+        ];
+
+        assert_eq!(schedule(&links), vec![vec![2], vec![0], vec![1]]);
+    }
+
+    #[test]
+    fn it_should_break_cycles_before_their_dependents() {
+        let links = [
+            ("/dotfiles/d", "/home/a/d"),
+            ("/home/b/x", "/home/a"),
+            ("/home/a/x", "/home/b"),
+        ];
+
+        assert_eq!(schedule(&links), vec![vec![1], vec![0, 2]]);
+    }
+}
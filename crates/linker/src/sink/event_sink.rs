@@ -0,0 +1,108 @@
+use std::{io::Error as IoError, sync::Arc};
+
+use crate::SkipReason;
+
+/// A single link scheduled by the linker
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LinkTask {
+    pub source: String,
+    pub target: String,
+    /// Position of the link in the original list
+    pub idx: usize,
+    /// Length of the original list
+    pub len: usize,
+}
+
+/// Receive the progress events of a linking run
+///
+/// For each task, `on_waiting` is called on the calling thread when the task
+/// is dispatched, the other events are called on a worker thread.
+/// The returned [`LinkEventSink::Handle`] is carried from one event to the next.
+///
+/// # Example
+///
+/// ```rust
+/// use ezcfg_linker::{LinkEventSink, LinkTask};
+/// use std::io::Error as IoError;
+///
+/// struct Printer;
+///
+/// impl LinkEventSink for Printer {
+///     type Handle = ();
+///
+///     fn on_waiting(&self, _task: &LinkTask) {}
+///     fn on_linking(&self, _task: &LinkTask, _handle: &()) {}
+///     fn on_success(&self, task: &LinkTask, _handle: ()) {
+///         println!("{} -> {}", task.source, task.target);
+///     }
+///     fn on_fail(&self, task: &LinkTask, _handle: (), err: &IoError) {
+///         println!("{} -> {}: {}", task.source, task.target, err);
+///     }
+/// }
+/// ```
+pub trait LinkEventSink: Send + Sync + 'static {
+    /// Per-task state handed from one event to the next
+    type Handle: Send + 'static;
+
+    /// The task has been dispatched and is waiting for a worker
+    fn on_waiting(&self, task: &LinkTask) -> Self::Handle;
+
+    /// A worker started linking the task
+    fn on_linking(&self, task: &LinkTask, handle: &Self::Handle);
+
+    /// The soft link has been created
+    fn on_success(&self, task: &LinkTask, handle: Self::Handle);
+
+    /// The soft link could not be created
+    fn on_fail(&self, task: &LinkTask, handle: Self::Handle, err: &IoError);
+
+    /// The task has been filtered out, it is called on the calling thread instead of `on_waiting`
+    fn on_skipped(&self, _task: &LinkTask, _reason: &SkipReason) {}
+
+    /// The run has been cancelled after the task has been dispatched, but before it started
+    fn on_cancelled(&self, _task: &LinkTask, _handle: Self::Handle) {}
+}
+
+impl<S: LinkEventSink> LinkEventSink for Arc<S> {
+    type Handle = S::Handle;
+
+    fn on_waiting(&self, task: &LinkTask) -> Self::Handle {
+        (**self).on_waiting(task)
+    }
+
+    fn on_linking(&self, task: &LinkTask, handle: &Self::Handle) {
+        (**self).on_linking(task, handle)
+    }
+
+    fn on_success(&self, task: &LinkTask, handle: Self::Handle) {
+        (**self).on_success(task, handle)
+    }
+
+    fn on_fail(&self, task: &LinkTask, handle: Self::Handle, err: &IoError) {
+        (**self).on_fail(task, handle, err)
+    }
+
+    fn on_skipped(&self, task: &LinkTask, reason: &SkipReason) {
+        (**self).on_skipped(task, reason)
+    }
+
+    fn on_cancelled(&self, task: &LinkTask, handle: Self::Handle) {
+        (**self).on_cancelled(task, handle)
+    }
+}
+
+/// A sink ignoring every event
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SilentSink;
+
+impl LinkEventSink for SilentSink {
+    type Handle = ();
+
+    fn on_waiting(&self, _task: &LinkTask) {}
+
+    fn on_linking(&self, _task: &LinkTask, _handle: &()) {}
+
+    fn on_success(&self, _task: &LinkTask, _handle: ()) {}
+
+    fn on_fail(&self, _task: &LinkTask, _handle: (), _err: &IoError) {}
+}
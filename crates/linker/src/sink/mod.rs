@@ -0,0 +1,12 @@
+//! This mod reports the progress of linking.
+//!
+//! The linker emits events to a [`LinkEventSink`],
+//! the `indicatif` spinners are just one implementation of it.
+
+mod event_sink;
+mod spinner;
+mod timing;
+
+pub use event_sink::{LinkEventSink, LinkTask, SilentSink};
+pub use spinner::SpinnerSink;
+pub use timing::{LinkTiming, TimedHandle, TimingSink};
@@ -3,12 +3,51 @@ use std::{io, path::PathBuf, sync::OnceLock, time::Duration};
 use ezcfg_cli::warn;
 use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
 
+use super::{LinkEventSink, LinkTask};
+use crate::SkipReason;
+
 static MULTI_PROGRESS: OnceLock<MultiProgress> = OnceLock::<MultiProgress>::new();
 fn mpb() -> &'static MultiProgress {
     #[allow(clippy::redundant_closure)]
     MULTI_PROGRESS.get_or_init(|| MultiProgress::new())
 }
 
+/// The default sink, rendering one `indicatif` spinner per link
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SpinnerSink;
+
+impl LinkEventSink for SpinnerSink {
+    type Handle = ProgressBar;
+
+    fn on_waiting(&self, task: &LinkTask) -> ProgressBar {
+        let pb = pb_setup();
+        pb_waiting(&pb, &task.source, &task.target, task.idx, task.len);
+        pb
+    }
+
+    fn on_linking(&self, task: &LinkTask, pb: &ProgressBar) {
+        pb_spinning(pb, &task.source, &task.target, task.idx, task.len);
+    }
+
+    fn on_success(&self, task: &LinkTask, pb: ProgressBar) {
+        pb_task_success(&pb, &task.source, &task.target, task.idx, task.len);
+    }
+
+    fn on_fail(&self, task: &LinkTask, pb: ProgressBar, err: &io::Error) {
+        pb_task_fail(&pb, &task.source, &task.target, task.idx, task.len, err);
+    }
+
+    fn on_skipped(&self, task: &LinkTask, reason: &SkipReason) {
+        let pb = pb_setup();
+        pb_task_skipped(&pb, &task.source, &task.target, task.idx, task.len, reason);
+    }
+
+    fn on_cancelled(&self, task: &LinkTask, pb: ProgressBar) {
+        let reason = SkipReason::Cancelled;
+        pb_task_skipped(&pb, &task.source, &task.target, task.idx, task.len, &reason);
+    }
+}
+
 pub fn pb_setup() -> ProgressBar {
     mpb().add(ProgressBar::new_spinner())
 }
@@ -58,6 +97,31 @@ pub fn pb_task_fail(
     ));
 }
 
+pub fn pb_task_skipped(
+    pb: &ProgressBar,
+    _source: &str,
+    target: &str,
+    idx: usize,
+    len: usize,
+    reason: &SkipReason,
+) {
+    let (template, prefix) = match reason {
+        SkipReason::UpToDate => ("{prefix:.bold.dim} {wide_msg:.dim}", "✔"),
+        SkipReason::Rejected(_) => ("{prefix:.bold.yellow} {wide_msg}", "-"),
+        SkipReason::Cancelled => ("{prefix:.bold.yellow} {wide_msg:.dim}", "-"),
+    };
+    let style = ProgressStyle::with_template(template).unwrap();
+    pb.set_style(style);
+    pb.set_prefix(prefix);
+    pb.finish_with_message(format!(
+        "[{idx}/{len}] Skipped {target}: {reason}",
+        idx = idx + 1,
+        len = len,
+        target = target,
+        reason = reason
+    ));
+}
+
 fn shorten_path(path: &str) -> String {
     let path = PathBuf::from(path);
     if path.is_dir() {
@@ -0,0 +1,158 @@
+use std::{
+    cmp::Reverse,
+    io::Error as IoError,
+    sync::{Mutex, OnceLock},
+    time::{Duration, Instant},
+};
+
+use super::{LinkEventSink, LinkTask};
+use crate::SkipReason;
+
+/// How long a link waited for a worker, and how long it took to create
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LinkTiming {
+    pub source: String,
+    pub target: String,
+    pub wait: Duration,
+    pub run: Duration,
+}
+
+/// A sink timing each link, before passing the events on to another sink
+///
+/// # Example
+///
+/// ```rust
+/// use ezcfg_linker::{Linker, MemoryBackend, SilentSink, TimingSink};
+/// use std::sync::Arc;
+///
+/// let sink = Arc::new(TimingSink::new(SilentSink));
+/// let links = vec![("/dotfiles/.zshrc".to_string(), "/home/.zshrc".to_string())];
+///
+/// Linker::new()
+///     .with_backend(MemoryBackend::new())
+///     .with_sink(sink.clone())
+///     .link_all(&links);
+///
+/// assert_eq!(sink.slowest(10)[0].target, "/home/.zshrc");
+/// ```
+pub struct TimingSink<S> {
+    inner: S,
+    timings: Mutex<Vec<LinkTiming>>,
+}
+
+/// The handle of the inner sink, along with when the task has been dispatched and started
+pub struct TimedHandle<H> {
+    inner: H,
+    queued_at: Instant,
+    started_at: OnceLock<Instant>,
+}
+
+impl<S: LinkEventSink> TimingSink<S> {
+    pub fn new(inner: S) -> Self {
+        TimingSink {
+            inner,
+            timings: Mutex::default(),
+        }
+    }
+
+    /// The `count` links which took the longest to create, the slowest first
+    pub fn slowest(&self, count: usize) -> Vec<LinkTiming> {
+        let mut timings = self.timings.lock().unwrap().clone();
+        timings.sort_by_key(|timing| Reverse(timing.run));
+        timings.truncate(count);
+        timings
+    }
+
+    fn record(&self, task: &LinkTask, handle: &TimedHandle<S::Handle>) {
+        let finished_at = Instant::now();
+        let started_at = *handle.started_at.get().unwrap_or(&finished_at);
+        self.timings.lock().unwrap().push(LinkTiming {
+            source: task.source.clone(),
+            target: task.target.clone(),
+            wait: started_at - handle.queued_at,
+            run: finished_at - started_at,
+        });
+    }
+}
+
+impl<S: LinkEventSink> LinkEventSink for TimingSink<S> {
+    type Handle = TimedHandle<S::Handle>;
+
+    fn on_waiting(&self, task: &LinkTask) -> Self::Handle {
+        TimedHandle {
+            inner: self.inner.on_waiting(task),
+            queued_at: Instant::now(),
+            started_at: OnceLock::new(),
+        }
+    }
+
+    fn on_linking(&self, task: &LinkTask, handle: &Self::Handle) {
+        let _ = handle.started_at.set(Instant::now());
+        self.inner.on_linking(task, &handle.inner);
+    }
+
+    fn on_success(&self, task: &LinkTask, handle: Self::Handle) {
+        self.record(task, &handle);
+        self.inner.on_success(task, handle.inner);
+    }
+
+    fn on_fail(&self, task: &LinkTask, handle: Self::Handle, err: &IoError) {
+        self.record(task, &handle);
+        self.inner.on_fail(task, handle.inner, err);
+    }
+
+    fn on_skipped(&self, task: &LinkTask, reason: &SkipReason) {
+        self.inner.on_skipped(task, reason);
+    }
+
+    fn on_cancelled(&self, task: &LinkTask, handle: Self::Handle) {
+        self.inner.on_cancelled(task, handle.inner);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Linker, MemoryBackend, SilentSink};
+
+    #[test]
+    fn it_should_time_every_link() {
+        let sink = std::sync::Arc::new(TimingSink::new(SilentSink));
+        let links = (0..5)
+            .map(|n| (format!("{}", n), format!("/home/{}", n)))
+            .collect::<Vec<_>>();
+
+        Linker::new()
+            .with_backend(MemoryBackend::new().with_file("taken"))
+            .with_sink(sink.clone())
+            .with_jobs(2)
+            .link_all(&links);
+
+        let slowest = sink.slowest(10);
+        assert_eq!(slowest.len(), 5);
+        assert!(slowest.windows(2).all(|pair| pair[0].run >= pair[1].run));
+        assert_eq!(sink.slowest(2).len(), 2);
+    }
+
+    #[test]
+    fn it_should_not_count_filtering_as_waiting() {
+        let sink = std::sync::Arc::new(TimingSink::new(SilentSink));
+        let links = (0..3)
+            .map(|n| (format!("{}", n), format!("/home/{}", n)))
+            .collect::<Vec<_>>();
+
+        Linker::new()
+            .with_backend(MemoryBackend::new())
+            .with_sink(sink.clone())
+            .with_jobs(1)
+            .link_all_with_filter(&links, |_, _, _, _| {
+                std::thread::sleep(Duration::from_millis(20));
+                true
+            });
+
+        assert!(sink
+            .slowest(3)
+            .iter()
+            .all(|timing| timing.wait < Duration::from_millis(20)));
+    }
+}
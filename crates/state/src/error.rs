@@ -0,0 +1,8 @@
+use std::path::PathBuf;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum StateError {
+    FailedToReadFile(PathBuf),
+    FailedToParseFile(PathBuf, String),
+    FailedToWriteFile(PathBuf),
+}
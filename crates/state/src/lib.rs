@@ -0,0 +1,5 @@
+mod error;
+mod state_struct;
+
+pub use error::StateError;
+pub use state_struct::*;
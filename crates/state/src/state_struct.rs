@@ -0,0 +1,255 @@
+use std::{
+    collections::BTreeMap,
+    env,
+    fs::{create_dir_all, read_to_string, rename, write},
+    io::ErrorKind as IoErrorKind,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::StateError::{self, *};
+
+/// How a target has been deployed
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LinkMode {
+    #[default]
+    Symlink,
+    /// A file decrypted from an encrypted source
+    Encrypted,
+}
+
+/// A link created by ezcfg
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LinkRecord {
+    pub source: String,
+    pub target: String,
+    pub mode: LinkMode,
+    /// Seconds since the unix epoch
+    pub created_at: u64,
+    /// The config file declaring the link
+    pub config: PathBuf,
+}
+
+impl LinkRecord {
+    pub fn new(source: &str, target: &str, mode: LinkMode, config: &Path) -> Self {
+        LinkRecord {
+            source: source.to_string(),
+            target: target.to_string(),
+            mode,
+            created_at: now(),
+            config: config.to_path_buf(),
+        }
+    }
+}
+
+/// Every link ezcfg knows it has created, keyed by target
+///
+/// # Example
+///
+/// ```rust
+/// use ezcfg_state::{LinkMode, LinkRecord, State};
+/// use std::path::Path;
+///
+/// let config = Path::new("/dotfiles/.ezcfg.toml");
+///
+/// let mut state = State::default();
+/// state.record(LinkRecord::new("/dotfiles/.zshrc", "/home/.zshrc", LinkMode::Symlink, config));
+/// state.record(LinkRecord::new("/dotfiles/.vimrc", "/home/.vimrc", LinkMode::Symlink, config));
+///
+/// assert!(state.owns("/home/.zshrc"));
+///
+/// // `.vimrc` has been dropped from the config
+/// let orphans = state.orphans(config, &["/home/.zshrc"]);
+/// assert_eq!(orphans.len(), 1);
+/// assert_eq!(orphans[0].target, "/home/.vimrc");
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct State {
+    links: BTreeMap<String, LinkRecord>,
+}
+
+impl State {
+    /// The state file, `$XDG_STATE_HOME/ezcfg/state.json`
+    /// or `$HOME/.local/state/ezcfg/state.json`
+    pub fn default_path() -> PathBuf {
+        let state_home = match env::var("XDG_STATE_HOME") {
+            Ok(dir) if !dir.is_empty() => PathBuf::from(dir),
+            _ => PathBuf::from(env::var("HOME").unwrap_or_default()).join(".local/state"),
+        };
+
+        state_home.join("ezcfg").join("state.json")
+    }
+
+    /// Read the state file, a missing file is an empty state
+    pub fn load(path: &Path) -> Result<Self, StateError> {
+        let content = match read_to_string(path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == IoErrorKind::NotFound => return Ok(State::default()),
+            Err(_) => return Err(FailedToReadFile(path.to_owned())),
+        };
+
+        serde_json::from_str(&content)
+            .map_err(|e| FailedToParseFile(path.to_owned(), e.to_string()))
+    }
+
+    /// Write the state file, creating its parent directories
+    ///
+    /// The content is written to a temporary file first,
+    /// so the state file is never left half-written.
+    pub fn save(&self, path: &Path) -> Result<(), StateError> {
+        let content = serde_json::to_string_pretty(self).unwrap();
+        let tmp_path = path.with_extension("json.tmp");
+
+        path.parent()
+            .map_or(Ok(()), create_dir_all)
+            .and_then(|_| write(&tmp_path, content))
+            .and_then(|_| rename(&tmp_path, path))
+            .map_err(|_| FailedToWriteFile(path.to_owned()))
+    }
+
+    /// Remember a link, replacing the previous record of its target
+    ///
+    /// The original creation time is kept if the target still points to the same source.
+    pub fn record(&mut self, record: LinkRecord) {
+        let record = match self.links.get(&record.target) {
+            Some(prev) if prev.source == record.source && prev.mode == record.mode => LinkRecord {
+                created_at: prev.created_at,
+                ..record
+            },
+            _ => record,
+        };
+
+        self.links.insert(record.target.clone(), record);
+    }
+
+    /// Forget the link at `target`
+    pub fn forget(&mut self, target: &str) -> Option<LinkRecord> {
+        self.links.remove(target)
+    }
+
+    pub fn get(&self, target: &str) -> Option<&LinkRecord> {
+        self.links.get(target)
+    }
+
+    /// Whether `target` has been created by ezcfg
+    pub fn owns(&self, target: &str) -> bool {
+        self.links.contains_key(target)
+    }
+
+    /// All records, ordered by target
+    pub fn links(&self) -> impl Iterator<Item = &LinkRecord> {
+        self.links.values()
+    }
+
+    /// Records created from the config file at `config`
+    pub fn links_from<'a>(&'a self, config: &'a Path) -> impl Iterator<Item = &'a LinkRecord> {
+        self.links().filter(move |record| record.config == config)
+    }
+
+    /// Records created from `config` whose target is no longer declared
+    pub fn orphans<S: AsRef<str>>(
+        &self,
+        config: &Path,
+        declared_targets: &[S],
+    ) -> Vec<&LinkRecord> {
+        self.links()
+            .filter(|record| record.config == config)
+            .filter(|record| {
+                !declared_targets
+                    .iter()
+                    .any(|target| target.as_ref() == record.target)
+            })
+            .collect()
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_fs::{prelude::*, TempDir};
+
+    use super::*;
+
+    fn record(source: &str, target: &str, config: &str) -> LinkRecord {
+        LinkRecord::new(source, target, LinkMode::Symlink, Path::new(config))
+    }
+
+    #[test]
+    fn it_should_load_missing_file_as_empty_state() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.child("state.json");
+
+        assert_eq!(State::load(path.path()).unwrap(), State::default());
+    }
+
+    #[test]
+    fn it_should_save_and_load() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.child("nested/ezcfg/state.json");
+
+        let mut state = State::default();
+        state.record(record("a", "b", "c"));
+        state.save(path.path()).unwrap();
+
+        assert_eq!(State::load(path.path()).unwrap(), state);
+        assert!(!path.path().with_extension("json.tmp").exists());
+    }
+
+    #[test]
+    fn it_should_report_unparsable_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.child("state.json");
+        path.write_str("not json").unwrap();
+
+        assert!(matches!(
+            State::load(path.path()),
+            Err(FailedToParseFile(..))
+        ));
+    }
+
+    #[test]
+    fn it_should_keep_creation_time_of_unchanged_link() {
+        let mut state = State::default();
+        state.record(LinkRecord {
+            created_at: 1,
+            ..record("a", "b", "c")
+        });
+
+        state.record(record("a", "b", "c"));
+        assert_eq!(state.get("b").unwrap().created_at, 1);
+
+        state.record(record("other", "b", "c"));
+        assert_ne!(state.get("b").unwrap().created_at, 1);
+    }
+
+    #[test]
+    fn it_should_find_orphans_of_a_config() {
+        let mut state = State::default();
+        state.record(record("a", "kept", "config"));
+        state.record(record("a", "dropped", "config"));
+        state.record(record("a", "elsewhere", "other-config"));
+
+        let orphans = state.orphans(Path::new("config"), &["kept"]);
+
+        assert_eq!(orphans, vec![state.get("dropped").unwrap()]);
+    }
+
+    #[test]
+    fn it_should_forget_link() {
+        let mut state = State::default();
+        state.record(record("a", "b", "c"));
+
+        assert!(state.forget("b").is_some());
+        assert!(!state.owns("b"));
+        assert!(state.forget("b").is_none());
+    }
+}
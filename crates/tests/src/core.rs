@@ -28,7 +28,6 @@ mod tests {
     test_with_config!(
         test_plenty_of_links,
         (0..=10000)
-            .into_iter()
             .map(|i| (format!("{}.txt", i), format!("{}", (i + 1))))
             .collect::<Vec<(String, String)>>()
     );
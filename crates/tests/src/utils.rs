@@ -58,7 +58,7 @@ where
     temp_source_dir.close().unwrap();
 }
 
-fn run_and_test_all_links(links: &Vec<(String, String)>) {
+fn run_and_test_all_links(links: &[(String, String)]) {
     link_all(links);
 
     links.iter().for_each(|(source, target)| {